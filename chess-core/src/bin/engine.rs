@@ -0,0 +1,294 @@
+//! A UCI engine built on the [`Searcher`], for playing in other chess GUIs
+//! and tournament managers.
+//!
+//! Besides the standard commands it has three options: `Hash`, the size of the
+//! transposition table in megabytes, `Clear Hash`, and `EvalFile`, a file of
+//! evaluation weights as written by the `tune` tool. It also offers
+//! `UCI_Chess960`, which switches castling moves between the king moving two
+//! squares and the king taking its own rook.
+
+use chess_core::{Limits, Params, Score, SearchResult, Searcher, State};
+use std::fs;
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const DEFAULT_HASH: usize = 16;
+const MAX_HASH: usize = 1024;
+// Time kept in hand for passing the move back to the GUI.
+const MARGIN: Duration = Duration::from_millis(50);
+
+struct Uci {
+    searcher: Option<Searcher>,
+    params: Params,
+    stop: Arc<AtomicBool>,
+    search: Option<Receiver<Searcher>>,
+    position: State,
+    history: Vec<u64>,
+    chess960: bool,
+}
+
+fn main() {
+    let mut uci = Uci::new();
+    for line in io::stdin().lock().lines() {
+        match line {
+            Ok(line) if uci.command(&line) => (),
+            _ => break,
+        }
+    }
+    uci.finish_search();
+}
+
+impl Uci {
+    fn new() -> Uci {
+        let searcher = Searcher::with_hash_size(DEFAULT_HASH);
+        Uci {
+            stop: searcher.stop_handle(),
+            searcher: Some(searcher),
+            params: Params::default(),
+            search: None,
+            position: State::new(),
+            history: Vec::new(),
+            chess960: false,
+        }
+    }
+
+    // Carries out a command, returning false once told to quit.
+    fn command(&mut self, line: &str) -> bool {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let args = words.get(1..).unwrap_or(&[]);
+        match words.first().copied() {
+            Some("uci") => {
+                println!(
+                    "id name {} {}",
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION")
+                );
+                println!(
+                    "id author {}",
+                    env!("CARGO_PKG_AUTHORS").split(" <").next().unwrap()
+                );
+                println!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_HASH, MAX_HASH
+                );
+                println!("option name Clear Hash type button");
+                println!("option name EvalFile type string default <empty>");
+                println!("option name UCI_Chess960 type check default false");
+                println!("uciok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("ucinewgame") => {
+                self.finish_search();
+                self.searcher.as_mut().unwrap().clear();
+            }
+            Some("setoption") => {
+                self.finish_search();
+                self.set_option(args);
+            }
+            Some("position") => {
+                self.finish_search();
+                self.set_position(args);
+            }
+            Some("go") => self.go(args),
+            Some("stop") => self.finish_search(),
+            Some("quit") => return false,
+            _ => (),
+        }
+        true
+    }
+
+    fn set_option(&mut self, args: &[&str]) {
+        let value = args.iter().position(|&word| word == "value");
+        let name = args[..value.unwrap_or(args.len())]
+            .iter()
+            .skip_while(|&&word| word == "name")
+            .copied()
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let value = value.map_or(String::new(), |i| args[i + 1..].join(" "));
+        match name.to_ascii_lowercase().as_str() {
+            "hash" => match value.parse::<usize>() {
+                Ok(megabytes) => {
+                    let mut searcher = Searcher::with_hash_size(megabytes.clamp(1, MAX_HASH));
+                    searcher.set_params(self.params.clone());
+                    self.stop = searcher.stop_handle();
+                    self.searcher = Some(searcher);
+                }
+                Err(_) => println!("info string invalid hash size '{}'", value),
+            },
+            "clear hash" => self.searcher.as_mut().unwrap().clear(),
+            "evalfile" => {
+                let params = if value.is_empty() || value == "<empty>" {
+                    Ok(Params::default())
+                } else {
+                    fs::read_to_string(&value)
+                        .map_err(|error| error.to_string())
+                        .and_then(|text| Params::parse(&text).map_err(|error| error.to_string()))
+                };
+                match params {
+                    Ok(params) => {
+                        self.searcher.as_mut().unwrap().set_params(params.clone());
+                        self.params = params;
+                    }
+                    Err(error) => println!("info string {}: {}", value, error),
+                }
+            }
+            "uci_chess960" => match value.as_str() {
+                "true" => self.chess960 = true,
+                "false" => self.chess960 = false,
+                _ => println!("info string invalid value '{}' for UCI_Chess960", value),
+            },
+            _ => println!("info string unknown option '{}'", name),
+        }
+    }
+
+    // Reads `startpos` or `fen ...`, followed by any moves played from there.
+    // The move counters may be left out of the FEN.
+    fn set_position(&mut self, args: &[&str]) {
+        let end = args
+            .iter()
+            .position(|&word| word == "moves")
+            .unwrap_or(args.len());
+        let mut state = match args.first() {
+            Some(&"startpos") => State::new(),
+            Some(&"fen") => {
+                let fen = args[1..end].join(" ");
+                match State::from_fen(&fen) {
+                    Ok(state) => state,
+                    Err(error) => {
+                        println!("info string invalid FEN: {}", error);
+                        return;
+                    }
+                }
+            }
+            _ => return,
+        };
+        state.set_chess960(self.chess960);
+        let mut history = Vec::new();
+        for uci in args.iter().skip(end + 1) {
+            match state.parse_uci(uci) {
+                Some(mv) => {
+                    history.push(state.hash());
+                    state.apply(mv);
+                }
+                None => {
+                    println!("info string illegal move '{}'", uci);
+                    break;
+                }
+            }
+        }
+        self.position = state;
+        self.history = history;
+    }
+
+    fn go(&mut self, args: &[&str]) {
+        self.finish_search();
+        let mut limits = Limits::default();
+        let mut clock = [None; 2];
+        let mut increment = [Duration::from_secs(0); 2];
+        let mut moves_to_go = None;
+        let mut infinite = false;
+        let mut words = args.iter();
+        while let Some(&word) = words.next() {
+            // GUIs sometimes send a negative time when a clock runs out.
+            let mut value = || {
+                words
+                    .next()
+                    .and_then(|value| value.parse::<i64>().ok())
+                    .map(|value| value.max(0) as u64)
+            };
+            match word {
+                "depth" => limits.depth = value().map(|depth| depth as u32),
+                "nodes" => limits.nodes = value(),
+                "movetime" => limits.time = value().map(Duration::from_millis),
+                "wtime" => clock[0] = value().map(Duration::from_millis),
+                "btime" => clock[1] = value().map(Duration::from_millis),
+                "winc" => increment[0] = Duration::from_millis(value().unwrap_or(0)),
+                "binc" => increment[1] = Duration::from_millis(value().unwrap_or(0)),
+                "movestogo" => moves_to_go = value(),
+                "infinite" => infinite = true,
+                _ => (),
+            }
+        }
+        let turn = self.position.get_turn() as usize;
+        if let (None, false, Some(remaining)) = (limits.time, infinite, clock[turn]) {
+            limits.time = Some(time_for_move(remaining, increment[turn], moves_to_go));
+        }
+
+        let mut searcher = self.searcher.take().unwrap();
+        searcher.set_history(&self.history);
+        let state = self.position.clone();
+        let stop = self.stop.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let result =
+                searcher.search_with(&state, limits, |result| println!("{}", info(result)));
+            // An infinite search only gives its move once it is stopped.
+            while infinite && !stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(5));
+            }
+            match (result.pv.first(), result.pv.get(1)) {
+                (Some(best), Some(ponder)) => println!("bestmove {} ponder {}", best, ponder),
+                (Some(best), None) => println!("bestmove {}", best),
+                _ => println!("bestmove 0000"),
+            }
+            let _ = sender.send(searcher);
+        });
+        self.search = Some(receiver);
+    }
+
+    // Stops any search in progress and waits for it to give its move. The
+    // searcher clears the stop flag when it starts, so the flag is set until
+    // the search has finished.
+    fn finish_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            loop {
+                self.stop.store(true, Ordering::Relaxed);
+                match search.recv_timeout(Duration::from_millis(10)) {
+                    Ok(searcher) => {
+                        self.searcher = Some(searcher);
+                        break;
+                    }
+                    Err(RecvTimeoutError::Timeout) => (),
+                    Err(RecvTimeoutError::Disconnected) => {
+                        let mut searcher = Searcher::with_hash_size(DEFAULT_HASH);
+                        searcher.set_params(self.params.clone());
+                        self.stop = searcher.stop_handle();
+                        self.searcher = Some(searcher);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Spends a share of the remaining time on each move, expecting the game to
+// last another 30 moves if the time control does not say.
+fn time_for_move(remaining: Duration, increment: Duration, moves_to_go: Option<u64>) -> Duration {
+    let moves = moves_to_go.unwrap_or(30).clamp(1, 30) as u32;
+    let time = remaining / moves + increment * 3 / 4;
+    time.min(remaining.checked_sub(MARGIN).unwrap_or_default())
+}
+
+fn info(result: &SearchResult) -> String {
+    let score = match result.score {
+        Score::Centipawns(centipawns) => format!("cp {}", centipawns),
+        Score::Mate(moves) => format!("mate {}", moves),
+    };
+    let millis = result.time.as_millis();
+    let pv: Vec<String> = result.pv.iter().map(|mv| mv.to_string()).collect();
+    format!(
+        "info depth {} score {} nodes {} nps {} time {} pv {}",
+        result.depth,
+        score,
+        result.nodes,
+        result.nodes as u128 * 1000 / millis.max(1),
+        millis,
+        pv.join(" ")
+    )
+}
@@ -0,0 +1,171 @@
+//! Tunes the evaluation weights to a set of positions from finished games, by
+//! minimising the error between the evaluation of each position and the
+//! result of its game (Texel's tuning method).
+//!
+//! ```text
+//! tune POSITIONS [PARAMS] [ITERATIONS] > eval.txt
+//! ```
+//!
+//! `POSITIONS` is an EPD file with the result of the game on each line, either
+//! as `c9 "1-0";` or in brackets as `[1.0]`, `[0.5]` or `[0.0]`, always from
+//! White's point of view. Tuning starts from the weights in `PARAMS`, or the
+//! defaults, and the tuned weights are written to standard output.
+
+use chess_core::{Params, State, Trace};
+use std::{env, fs, process};
+
+const LEARNING_RATE: f64 = 1.0;
+const BETA1: f64 = 0.9;
+const BETA2: f64 = 0.999;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 || args.len() > 4 {
+        eprintln!("usage: tune POSITIONS [PARAMS] [ITERATIONS]");
+        process::exit(2);
+    }
+    let text = read(&args[1]);
+    let mut params = match args.get(2) {
+        Some(path) => Params::parse(&read(path)).unwrap_or_else(|error| {
+            fail(&format!("{}: {}", path, error));
+        }),
+        None => Params::default(),
+    };
+    let iterations = match args.get(3) {
+        Some(arg) => arg
+            .parse()
+            .unwrap_or_else(|_| fail(&format!("invalid number of iterations '{}'", arg))),
+        None => 1000,
+    };
+
+    let mut positions = Vec::new();
+    let mut skipped = 0;
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        match parse_position(line) {
+            Some(position) => positions.push(position),
+            None => skipped += 1,
+        }
+    }
+    if positions.is_empty() {
+        fail("no positions to tune with");
+    }
+    eprintln!("{} positions, {} lines skipped", positions.len(), skipped);
+
+    let mut weights: Vec<[f64; 2]> = params
+        .weights()
+        .iter()
+        .map(|&[middlegame, endgame]| [middlegame as f64, endgame as f64])
+        .collect();
+    let scale = fit_scale(&positions, &weights);
+    eprintln!(
+        "scale {:.3}, error {:.6}",
+        scale,
+        error(&positions, &weights, scale)
+    );
+
+    let mut momentum = vec![[0.0; 2]; weights.len()];
+    let mut velocity = vec![[0.0; 2]; weights.len()];
+    for iteration in 1..=iterations {
+        let gradient = gradient(&positions, &weights, scale);
+        let correction1 = 1.0 - BETA1.powi(iteration);
+        let correction2 = 1.0 - BETA2.powi(iteration);
+        for i in 0..weights.len() {
+            for phase in 0..2 {
+                let g = gradient[i][phase];
+                momentum[i][phase] = BETA1 * momentum[i][phase] + (1.0 - BETA1) * g;
+                velocity[i][phase] = BETA2 * velocity[i][phase] + (1.0 - BETA2) * g * g;
+                weights[i][phase] -= LEARNING_RATE * momentum[i][phase]
+                    / correction1
+                    / ((velocity[i][phase] / correction2).sqrt() + 1e-8);
+            }
+        }
+        if iteration % 50 == 0 || iteration == iterations {
+            eprintln!(
+                "iteration {}, error {:.6}",
+                iteration,
+                error(&positions, &weights, scale)
+            );
+        }
+    }
+
+    for (weight, tuned) in params.weights_mut().iter_mut().zip(weights.iter()) {
+        *weight = [tuned[0].round() as i32, tuned[1].round() as i32];
+    }
+    print!("{}", params);
+}
+
+fn read(path: &str) -> String {
+    fs::read_to_string(path).unwrap_or_else(|error| fail(&format!("{}: {}", path, error)))
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("tune: {}", message);
+    process::exit(1);
+}
+
+// Reads the position and result from an EPD line. The move counters are not
+// part of EPD and do not matter to the evaluation.
+fn parse_position(line: &str) -> Option<(Trace, f64)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 5 {
+        return None;
+    }
+    let state = State::from_fen(&format!("{} 0 1", fields[..4].join(" "))).ok()?;
+    let rest = fields[4..].join(" ");
+    let result = if rest.contains("1/2-1/2") || rest.contains("[0.5]") {
+        0.5
+    } else if rest.contains("1-0") || rest.contains("[1.0]") {
+        1.0
+    } else if rest.contains("0-1") || rest.contains("[0.0]") {
+        0.0
+    } else {
+        return None;
+    };
+    Some((Trace::new(&state), result))
+}
+
+// The expected score for White given an evaluation in centipawns.
+fn sigmoid(eval: f64, scale: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-scale * eval / 400.0))
+}
+
+fn error(positions: &[(Trace, f64)], weights: &[[f64; 2]], scale: f64) -> f64 {
+    let total: f64 = positions
+        .iter()
+        .map(|(trace, result)| (result - sigmoid(trace.evaluate(weights), scale)).powi(2))
+        .sum();
+    total / positions.len() as f64
+}
+
+// Finds the scale that best fits the starting weights to the results, so that
+// tuning changes the weights rather than the meaning of a centipawn.
+fn fit_scale(positions: &[(Trace, f64)], weights: &[[f64; 2]]) -> f64 {
+    let (mut low, mut high) = (0.0, 4.0);
+    for _ in 0..50 {
+        let third = (high - low) / 3.0;
+        if error(positions, weights, low + third) < error(positions, weights, high - third) {
+            high -= third;
+        } else {
+            low += third;
+        }
+    }
+    (low + high) / 2.0
+}
+
+fn gradient(positions: &[(Trace, f64)], weights: &[[f64; 2]], scale: f64) -> Vec<[f64; 2]> {
+    let mut gradient = vec![[0.0; 2]; weights.len()];
+    for (trace, result) in positions.iter() {
+        let expected = sigmoid(trace.evaluate(weights), scale);
+        let slope = (expected - result) * expected * (1.0 - expected);
+        for &(index, count) in trace.terms.iter() {
+            gradient[index][0] += slope * count as f64 * trace.phase;
+            gradient[index][1] += slope * count as f64 * (1.0 - trace.phase);
+        }
+    }
+    let factor = 2.0 * scale * 10f64.ln() / 400.0 / positions.len() as f64;
+    for weight in gradient.iter_mut() {
+        weight[0] *= factor;
+        weight[1] *= factor;
+    }
+    gradient
+}
@@ -0,0 +1,273 @@
+use crate::*;
+
+pub type Bitboard = u64;
+
+const NORTH: usize = 0;
+const NORTH_EAST: usize = 1;
+const EAST: usize = 2;
+const NORTH_WEST: usize = 3;
+const SOUTH: usize = 4;
+const SOUTH_WEST: usize = 5;
+const WEST: usize = 6;
+const SOUTH_EAST: usize = 7;
+
+const DIRECTIONS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (-1, 1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (1, -1),
+];
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (2, 1),
+    (1, 2),
+    (-1, 2),
+    (-2, 1),
+    (-2, -1),
+    (-1, -2),
+    (1, -2),
+    (2, -1),
+];
+
+const fn offset_table(offsets: &[(i8, i8)]) -> [Bitboard; 64] {
+    let mut table = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let x = (square & 7) as i8;
+        let y = (square >> 3) as i8;
+        let mut i = 0;
+        while i < offsets.len() {
+            let (tx, ty) = (x + offsets[i].0, y + offsets[i].1);
+            if tx >= 0 && tx < 8 && ty >= 0 && ty < 8 {
+                table[square] |= 1 << (ty * 8 + tx);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+const fn ray_table() -> [[Bitboard; 64]; 8] {
+    let mut table = [[0; 64]; 8];
+    let mut direction = 0;
+    while direction < 8 {
+        let (dx, dy) = DIRECTIONS[direction];
+        let mut square = 0;
+        while square < 64 {
+            let mut x = (square & 7) as i8 + dx;
+            let mut y = (square >> 3) as i8 + dy;
+            while x >= 0 && x < 8 && y >= 0 && y < 8 {
+                table[direction][square] |= 1 << (y * 8 + x);
+                x += dx;
+                y += dy;
+            }
+            square += 1;
+        }
+        direction += 1;
+    }
+    table
+}
+
+const KNIGHT_ATTACKS: [Bitboard; 64] = offset_table(&KNIGHT_OFFSETS);
+const KING_ATTACKS: [Bitboard; 64] = offset_table(&DIRECTIONS);
+const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    offset_table(&[(-1, 1), (1, 1)]),
+    offset_table(&[(-1, -1), (1, -1)]),
+];
+const RAYS: [[Bitboard; 64]; 8] = ray_table();
+
+fn ray_attacks(square: usize, occupied: Bitboard, direction: usize) -> Bitboard {
+    let ray = RAYS[direction][square];
+    let blockers = ray & occupied;
+    if blockers == 0 {
+        return ray;
+    }
+    let blocker = if direction < SOUTH {
+        blockers.trailing_zeros()
+    } else {
+        63 - blockers.leading_zeros()
+    };
+    ray ^ RAYS[direction][blocker as usize]
+}
+
+pub fn knight_attacks(pos: Position) -> Bitboard {
+    KNIGHT_ATTACKS[pos.value as usize]
+}
+
+pub fn king_attacks(pos: Position) -> Bitboard {
+    KING_ATTACKS[pos.value as usize]
+}
+
+pub fn pawn_attacks(pos: Position, colour: Colour) -> Bitboard {
+    PAWN_ATTACKS[colour as usize][pos.value as usize]
+}
+
+pub fn bishop_attacks(pos: Position, occupied: Bitboard) -> Bitboard {
+    let square = pos.value as usize;
+    ray_attacks(square, occupied, NORTH_EAST)
+        | ray_attacks(square, occupied, NORTH_WEST)
+        | ray_attacks(square, occupied, SOUTH_EAST)
+        | ray_attacks(square, occupied, SOUTH_WEST)
+}
+
+pub fn rook_attacks(pos: Position, occupied: Bitboard) -> Bitboard {
+    let square = pos.value as usize;
+    ray_attacks(square, occupied, NORTH)
+        | ray_attacks(square, occupied, EAST)
+        | ray_attacks(square, occupied, SOUTH)
+        | ray_attacks(square, occupied, WEST)
+}
+
+pub fn between(a: Position, b: Position) -> Bitboard {
+    let straight = rook_attacks(a, bit(b));
+    if straight & bit(b) != 0 {
+        return straight & rook_attacks(b, bit(a));
+    }
+    let diagonal = bishop_attacks(a, bit(b));
+    if diagonal & bit(b) != 0 {
+        return diagonal & bishop_attacks(b, bit(a));
+    }
+    0
+}
+
+pub fn bit(pos: Position) -> Bitboard {
+    1 << pos.value
+}
+
+pub fn squares(mut bitboard: Bitboard) -> impl Iterator<Item = Position> {
+    std::iter::from_fn(move || {
+        if bitboard == 0 {
+            return None;
+        }
+        let value = bitboard.trailing_zeros() as u8;
+        bitboard &= bitboard - 1;
+        Some(Position { value })
+    })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Board {
+    colours: [Bitboard; 2],
+    variants: [Bitboard; 6],
+    squares: [Option<Piece>; 64],
+    hash: u64,
+}
+
+impl Board {
+    pub fn empty() -> Board {
+        Board {
+            colours: [0; 2],
+            variants: [0; 6],
+            squares: [None; 64],
+            hash: 0,
+        }
+    }
+
+    pub fn get(&self, pos: Position) -> Option<Piece> {
+        self.squares[pos.value as usize]
+    }
+
+    pub fn set(&mut self, pos: Position, piece: Piece) {
+        self.remove(pos);
+        self.colours[piece.colour as usize] |= bit(pos);
+        self.variants[piece.variant as usize] |= bit(pos);
+        self.squares[pos.value as usize] = Some(piece);
+        self.hash ^= zobrist::piece(pos, piece);
+    }
+
+    pub fn remove(&mut self, pos: Position) -> Option<Piece> {
+        let piece = self.squares[pos.value as usize].take()?;
+        self.colours[piece.colour as usize] &= !bit(pos);
+        self.variants[piece.variant as usize] &= !bit(pos);
+        self.hash ^= zobrist::piece(pos, piece);
+        Some(piece)
+    }
+
+    pub fn move_piece(&mut self, from: Position, to: Position) {
+        if let Some(piece) = self.remove(from) {
+            self.set(to, piece);
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.colours[0] | self.colours[1]
+    }
+
+    pub fn colour(&self, colour: Colour) -> Bitboard {
+        self.colours[colour as usize]
+    }
+
+    pub fn variant(&self, variant: Variant) -> Bitboard {
+        self.variants[variant as usize]
+    }
+
+    pub fn pieces(&self, colour: Colour, variant: Variant) -> Bitboard {
+        self.colours[colour as usize] & self.variants[variant as usize]
+    }
+
+    pub fn king(&self, colour: Colour) -> Option<Position> {
+        squares(self.pieces(colour, King)).next()
+    }
+
+    pub fn attacks(&self, pos: Position, piece: Piece) -> Bitboard {
+        match piece.variant {
+            Pawn => pawn_attacks(pos, piece.colour),
+            Knight => knight_attacks(pos),
+            Bishop => bishop_attacks(pos, self.occupied()),
+            Rook => rook_attacks(pos, self.occupied()),
+            Queen => bishop_attacks(pos, self.occupied()) | rook_attacks(pos, self.occupied()),
+            King => king_attacks(pos),
+        }
+    }
+
+    pub fn attackers(&self, pos: Position, colour: Colour) -> Bitboard {
+        let occupied = self.occupied();
+        let diagonal = self.variant(Bishop) | self.variant(Queen);
+        let straight = self.variant(Rook) | self.variant(Queen);
+        ((pawn_attacks(pos, colour.flipped()) & self.variant(Pawn))
+            | (knight_attacks(pos) & self.variant(Knight))
+            | (king_attacks(pos) & self.variant(King))
+            | (bishop_attacks(pos, occupied) & diagonal)
+            | (rook_attacks(pos, occupied) & straight))
+            & self.colour(colour)
+    }
+
+    pub fn is_attacked(&self, pos: Position, colour: Colour) -> bool {
+        self.attackers(pos, colour) != 0
+    }
+
+    pub fn in_check(&self, colour: Colour) -> bool {
+        match self.king(colour) {
+            Some(king) => self.is_attacked(king, colour.flipped()),
+            None => false,
+        }
+    }
+
+    pub fn pinned(&self, king: Position, colour: Colour) -> Bitboard {
+        let enemy = self.colour(colour.flipped());
+        let snipers = ((rook_attacks(king, enemy) & (self.variant(Rook) | self.variant(Queen)))
+            | (bishop_attacks(king, enemy) & (self.variant(Bishop) | self.variant(Queen))))
+            & enemy;
+        let mut pinned = 0;
+        for sniper in squares(snipers) {
+            let blockers = between(king, sniper) & self.occupied();
+            if blockers.count_ones() == 1 {
+                pinned |= blockers & self.colour(colour);
+            }
+        }
+        pinned
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Position, Piece)> + '_ {
+        squares(self.occupied()).map(move |pos| (pos, self.squares[pos.value as usize].unwrap()))
+    }
+}
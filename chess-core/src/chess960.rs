@@ -0,0 +1,77 @@
+use crate::*;
+
+// The two knights' places among the five squares still empty once the
+// bishops and queen are placed, in the order of the standard numbering.
+const KNIGHTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+impl State {
+    /// The starting position numbered `number` in Chess960, also known as
+    /// Fischer Random Chess, or `None` if it is not below 960. Positions are
+    /// numbered as in Scharnagl's scheme, where 518 is the standard starting
+    /// position.
+    pub fn chess960(number: u16) -> Option<State> {
+        if number >= 960 {
+            return None;
+        }
+        let mut rank = [None; 8];
+        let mut n = number as usize;
+        rank[n % 4 * 2 + 1] = Some(Bishop);
+        n /= 4;
+        rank[n % 4 * 2] = Some(Bishop);
+        n /= 4;
+        place(&mut rank, n % 6, Queen);
+        n /= 6;
+        let (first, second) = KNIGHTS[n];
+        place(&mut rank, second, Knight);
+        place(&mut rank, first, Knight);
+        // The king goes between the rooks on the three squares left.
+        for &variant in [Rook, King, Rook].iter() {
+            place(&mut rank, 0, variant);
+        }
+
+        let white: String = rank
+            .iter()
+            .map(|variant| variant.unwrap().to_char())
+            .collect();
+        let fen = format!(
+            "{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1",
+            white.to_ascii_lowercase(),
+            white
+        );
+        Some(State::from_fen(&fen).unwrap())
+    }
+
+    /// Whether castling follows the Chess960 rules, which is the case when a
+    /// king or rook that may castle is not on its standard starting square
+    /// unless changed with [`State::set_chess960`]. Castling moves are then
+    /// written as the king moving to its rook's square, as in the Chess960
+    /// mode of UCI.
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    /// Switches castling moves between the Chess960 form of the king moving
+    /// to its rook's square and the standard form of the king moving two
+    /// squares, as a UCI engine must when told to by `UCI_Chess960`.
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.chess960 = chess960;
+        self.gen_legal_moves();
+    }
+}
+
+// Puts a piece on the empty square `index` places from the a-file.
+fn place(rank: &mut [Option<Variant>; 8], index: usize, variant: Variant) {
+    let square = (0..8).filter(|&x| rank[x].is_none()).nth(index).unwrap();
+    rank[square] = Some(variant);
+}
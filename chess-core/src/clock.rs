@@ -0,0 +1,245 @@
+use crate::*;
+use std::time::Duration;
+
+/// Extra time a player gets for each move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bonus {
+    /// No extra time.
+    None,
+    /// Fischer increment: added to the clock after every move.
+    Increment(Duration),
+    /// Bronstein delay: the time used for a move is given back after it, up
+    /// to this much.
+    Bronstein(Duration),
+    /// Simple delay: the clock only starts counting down after this much of
+    /// each move has passed.
+    Delay(Duration),
+}
+
+/// One period of a time control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stage {
+    /// The number of moves to be made in this period, or `None` for the rest
+    /// of the game.
+    pub moves: Option<u32>,
+    /// The time added to the clock at the start of the period.
+    pub time: Duration,
+    /// The extra time for each move made in the period.
+    pub bonus: Bonus,
+}
+
+/// The time allowed for a game, as one or more stages.
+///
+/// Written in the style of the PGN `TimeControl` tag, with times in seconds:
+/// stages are separated by `:`, a move count comes before a `/`, and a bonus
+/// follows the time as `+` for an increment, `d` for a simple delay or `b`
+/// for a Bronstein delay. For example `40/5400+30:1800+30` is 90 minutes for
+/// 40 moves, then 30 minutes for the rest of the game, with 30 seconds added
+/// per move throughout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeControl {
+    /// The stages in the order they are played. If the last stage has a move
+    /// count it repeats.
+    pub stages: Vec<Stage>,
+}
+
+impl TimeControl {
+    /// A single period for the whole game with no extra time per move.
+    pub fn sudden_death(time: Duration) -> TimeControl {
+        TimeControl::single(time, Bonus::None)
+    }
+
+    /// A single period with a Fischer increment.
+    pub fn fischer(time: Duration, increment: Duration) -> TimeControl {
+        TimeControl::single(time, Bonus::Increment(increment))
+    }
+
+    /// A single period with a Bronstein delay.
+    pub fn bronstein(time: Duration, delay: Duration) -> TimeControl {
+        TimeControl::single(time, Bonus::Bronstein(delay))
+    }
+
+    /// A single period with a simple delay.
+    pub fn delay(time: Duration, delay: Duration) -> TimeControl {
+        TimeControl::single(time, Bonus::Delay(delay))
+    }
+
+    fn single(time: Duration, bonus: Bonus) -> TimeControl {
+        TimeControl {
+            stages: vec![Stage {
+                moves: None,
+                time,
+                bonus,
+            }],
+        }
+    }
+
+    /// Parses a time control such as `300+2` or `40/5400:1800d5`.
+    pub fn parse(text: &str) -> Option<TimeControl> {
+        let mut stages = Vec::new();
+        for stage in text.split(':') {
+            let (moves, rest) = match stage.find('/') {
+                Some(i) => (Some(stage[..i].parse().ok()?), &stage[i + 1..]),
+                None => (None, stage),
+            };
+            let (time, bonus) = match rest.find(&['+', 'd', 'b'][..]) {
+                Some(i) => {
+                    let extra = Duration::from_secs(rest[i + 1..].parse().ok()?);
+                    let bonus = match &rest[i..i + 1] {
+                        "+" => Bonus::Increment(extra),
+                        "d" => Bonus::Delay(extra),
+                        _ => Bonus::Bronstein(extra),
+                    };
+                    (&rest[..i], bonus)
+                }
+                None => (rest, Bonus::None),
+            };
+            if moves == Some(0) {
+                return None;
+            }
+            stages.push(Stage {
+                moves,
+                time: Duration::from_secs(time.parse().ok()?),
+                bonus,
+            });
+        }
+        Some(TimeControl { stages })
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, stage) in self.stages.iter().enumerate() {
+            if i > 0 {
+                write!(f, ":")?;
+            }
+            if let Some(moves) = stage.moves {
+                write!(f, "{}/", moves)?;
+            }
+            write!(f, "{}", stage.time.as_secs())?;
+            match stage.bonus {
+                Bonus::None => (),
+                Bonus::Increment(extra) => write!(f, "+{}", extra.as_secs())?,
+                Bonus::Delay(extra) => write!(f, "d{}", extra.as_secs())?,
+                Bonus::Bronstein(extra) => write!(f, "b{}", extra.as_secs())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A chess clock running a [`TimeControl`].
+///
+/// The clock starts when the first move is made. Time passes for the player
+/// whose turn it is through [`Clock::tick`], and [`Clock::press`] ends a
+/// player's turn, adding any bonus and starting the next stage when one is
+/// complete.
+#[derive(Debug, Clone)]
+pub struct Clock {
+    control: TimeControl,
+    remaining: [Duration; 2],
+    stage: [usize; 2],
+    moves: [u32; 2],
+    turn: Colour,
+    elapsed: Duration,
+    started: bool,
+    flagged: Option<Colour>,
+}
+
+impl Clock {
+    /// A clock that has not yet started.
+    pub fn new(control: TimeControl) -> Clock {
+        let time = control.stages[0].time;
+        Clock {
+            control,
+            remaining: [time; 2],
+            stage: [0; 2],
+            moves: [0; 2],
+            turn: White,
+            elapsed: Duration::from_secs(0),
+            started: false,
+            flagged: None,
+        }
+    }
+
+    /// The time control the clock is running.
+    pub fn control(&self) -> &TimeControl {
+        &self.control
+    }
+
+    /// The time a player has left, including time passed in the current turn.
+    pub fn remaining(&self, colour: Colour) -> Duration {
+        let remaining = self.remaining[colour as usize];
+        if self.started && colour == self.turn {
+            remaining.saturating_sub(self.used())
+        } else {
+            remaining
+        }
+    }
+
+    /// The player whose time is running.
+    pub fn turn(&self) -> Colour {
+        self.turn
+    }
+
+    /// Whether the first move has been made.
+    pub fn started(&self) -> bool {
+        self.started
+    }
+
+    /// The player who ran out of time, if one has.
+    pub fn flagged(&self) -> Option<Colour> {
+        self.flagged
+    }
+
+    /// Lets time pass for the player to move, returning the player who has
+    /// run out of time, if any.
+    pub fn tick(&mut self, elapsed: Duration) -> Option<Colour> {
+        if self.started && self.flagged.is_none() {
+            self.elapsed += elapsed;
+            if self.used() > self.remaining[self.turn as usize] {
+                self.flagged = Some(self.turn);
+            }
+        }
+        self.flagged
+    }
+
+    /// Ends the turn of `colour` after they have moved, starting the clock
+    /// for their opponent.
+    pub fn press(&mut self, colour: Colour) {
+        if self.flagged.is_some() {
+            return;
+        }
+        let i = colour as usize;
+        if self.started && colour == self.turn {
+            let stage = self.control.stages[self.stage[i]];
+            let remaining = self.remaining[i].saturating_sub(self.used());
+            self.remaining[i] = match stage.bonus {
+                Bonus::None | Bonus::Delay(_) => remaining,
+                Bonus::Increment(extra) => remaining + extra,
+                Bonus::Bronstein(extra) => remaining + extra.min(self.elapsed),
+            };
+        }
+        self.started = true;
+
+        self.moves[i] += 1;
+        if Some(self.moves[i]) == self.control.stages[self.stage[i]].moves {
+            self.moves[i] = 0;
+            if self.stage[i] + 1 < self.control.stages.len() {
+                self.stage[i] += 1;
+            }
+            self.remaining[i] += self.control.stages[self.stage[i]].time;
+        }
+
+        self.turn = colour.flipped();
+        self.elapsed = Duration::from_secs(0);
+    }
+
+    // The time taken off the clock so far this turn.
+    fn used(&self) -> Duration {
+        match self.control.stages[self.stage[self.turn as usize]].bonus {
+            Bonus::Delay(delay) => self.elapsed.saturating_sub(delay),
+            _ => self.elapsed,
+        }
+    }
+}
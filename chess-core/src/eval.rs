@@ -0,0 +1,540 @@
+use crate::bitboard::*;
+use crate::*;
+use std::error::Error;
+
+// The values used to order captures in the search, which are not tuned.
+const VALUES: [i32; 6] = [100, 320, 330, 500, 900, 0];
+
+// The phase weight of each piece. A full set of pieces is a phase of 24, the
+// middlegame, and bare kings and pawns are 0, the endgame.
+const PHASES: [i32; 6] = [0, 1, 1, 2, 4, 0];
+const MAX_PHASE: i32 = 24;
+
+const FILE: Bitboard = 0x0101_0101_0101_0101;
+
+// Where each parameter starts in the weights.
+const VALUE: usize = 0;
+const TABLE: usize = VALUE + 6;
+const MOBILITY: usize = TABLE + 6 * 64;
+const DOUBLED_PAWN: usize = MOBILITY + 4;
+const ISOLATED_PAWN: usize = DOUBLED_PAWN + 1;
+const PASSED_PAWN: usize = ISOLATED_PAWN + 1;
+const PAWN_SHIELD: usize = PASSED_PAWN + 8;
+const KING_ATTACK: usize = PAWN_SHIELD + 1;
+const BISHOP_PAIR: usize = KING_ATTACK + 1;
+const WEIGHTS: usize = BISHOP_PAIR + 1;
+
+const NAMES: [&str; 6] = ["pawn", "knight", "bishop", "rook", "queen", "king"];
+
+// The default piece-square tables, from White's point of view with the
+// eighth rank first.
+#[rustfmt::skip]
+const PAWN: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING_MIDDLEGAME: [i32; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+/// The material value of a piece in centipawns, for ordering captures.
+pub(crate) fn value(variant: Variant) -> i32 {
+    VALUES[variant as usize]
+}
+
+/// The weights of a static evaluation, each a pair of a middlegame and an
+/// endgame value in centipawns. The two are blended by how much material is
+/// left on the board.
+///
+/// The evaluation counts material, piece-square tables, the mobility of the
+/// pieces, doubled, isolated and passed pawns, the pawns sheltering each king,
+/// attacks on the squares around each king and the bishop pair.
+///
+/// Parameters are written as `key = values` lines, where each value is
+/// `middlegame/endgame`, or a single number for both. Piece-square tables have
+/// 64 values from White's point of view, with the eighth rank first, and
+/// passed pawn bonuses have 8, one for each rank. `#` starts a comment.
+///
+/// ```
+/// use chess_core::Params;
+///
+/// let params = Params::parse("value.queen = 1000/950\nbishop.pair = 40").unwrap();
+/// assert_eq!(params.to_string().lines().find(|line| line.starts_with("bishop.pair")),
+///     Some("bishop.pair = 40"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params {
+    weights: Vec<[i32; 2]>,
+}
+
+impl Default for Params {
+    fn default() -> Params {
+        let mut weights = vec![[0, 0]; WEIGHTS];
+        let values = [
+            [82, 94],
+            [337, 281],
+            [365, 297],
+            [477, 512],
+            [1025, 936],
+            [0, 0],
+        ];
+        let tables = [
+            (&PAWN, &PAWN),
+            (&KNIGHT, &KNIGHT),
+            (&BISHOP, &BISHOP),
+            (&ROOK, &ROOK),
+            (&QUEEN, &QUEEN),
+            (&KING_MIDDLEGAME, &KING_ENDGAME),
+        ];
+        for (variant, (middlegame, endgame)) in tables.iter().enumerate() {
+            weights[VALUE + variant] = values[variant];
+            for square in 0..64 {
+                weights[TABLE + variant * 64 + square] = [middlegame[square], endgame[square]];
+            }
+        }
+        weights[MOBILITY..MOBILITY + 4].copy_from_slice(&[[4, 4], [5, 5], [2, 4], [1, 2]]);
+        weights[DOUBLED_PAWN] = [-10, -20];
+        weights[ISOLATED_PAWN] = [-10, -15];
+        weights[PASSED_PAWN..PASSED_PAWN + 8].copy_from_slice(&[
+            [0, 0],
+            [5, 10],
+            [10, 20],
+            [15, 35],
+            [25, 60],
+            [40, 90],
+            [60, 130],
+            [0, 0],
+        ]);
+        weights[PAWN_SHIELD] = [10, 0];
+        weights[KING_ATTACK] = [-8, -2];
+        weights[BISHOP_PAIR] = [30, 50];
+        Params { weights }
+    }
+}
+
+impl Params {
+    /// Reads parameters in the format described above. Any that are not given
+    /// keep their default values.
+    pub fn parse(text: &str) -> Result<Params, ParamsError> {
+        let sections = sections();
+        let mut params = Params::default();
+        let mut current: Option<(usize, usize, Vec<[i32; 2]>)> = None;
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let values = match line.find('=') {
+                Some(equals) => {
+                    if let Some(section) = current.take() {
+                        params.set(&sections, section)?;
+                    }
+                    let key = line[..equals].trim();
+                    let index = sections
+                        .iter()
+                        .position(|(name, _, _)| name == key)
+                        .ok_or_else(|| {
+                            ParamsError::new(
+                                line_number,
+                                ParamsErrorKind::UnknownKey(key.to_string()),
+                            )
+                        })?;
+                    current = Some((index, line_number, Vec::new()));
+                    &line[equals + 1..]
+                }
+                None => line,
+            };
+            let (_, _, weights) = current
+                .as_mut()
+                .ok_or_else(|| ParamsError::new(line_number, ParamsErrorKind::MissingKey))?;
+            for token in values.split_whitespace() {
+                weights.push(parse_weight(token).ok_or_else(|| {
+                    ParamsError::new(
+                        line_number,
+                        ParamsErrorKind::InvalidValue(token.to_string()),
+                    )
+                })?);
+            }
+        }
+        if let Some(section) = current {
+            params.set(&sections, section)?;
+        }
+        Ok(params)
+    }
+
+    fn set(
+        &mut self,
+        sections: &[(String, usize, usize)],
+        (index, line, weights): (usize, usize, Vec<[i32; 2]>),
+    ) -> Result<(), ParamsError> {
+        let (_, start, len) = sections[index];
+        if weights.len() != len {
+            return Err(ParamsError::new(
+                line,
+                ParamsErrorKind::WrongLength {
+                    expected: len,
+                    found: weights.len(),
+                },
+            ));
+        }
+        self.weights[start..start + len].copy_from_slice(&weights);
+        Ok(())
+    }
+
+    /// Every weight, in the order [`Trace`] refers to them.
+    pub fn weights(&self) -> &[[i32; 2]] {
+        &self.weights
+    }
+
+    /// Every weight, for changing them while tuning.
+    pub fn weights_mut(&mut self) -> &mut [[i32; 2]] {
+        &mut self.weights
+    }
+
+    /// Evaluates the position in centipawns from the point of view of the side
+    /// to move.
+    pub fn evaluate(&self, state: &State) -> i32 {
+        let mut score = [0, 0];
+        let phase = features(state, |index, count| {
+            score[0] += self.weights[index][0] * count;
+            score[1] += self.weights[index][1] * count;
+        });
+        let score = (score[0] * phase + score[1] * (MAX_PHASE - phase)) / MAX_PHASE;
+        if state.turn == White {
+            score
+        } else {
+            -score
+        }
+    }
+}
+
+impl fmt::Display for Params {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# Weights are middlegame/endgame, in centipawns.")?;
+        for (name, start, len) in sections() {
+            let values = self.weights[start..start + len]
+                .iter()
+                .map(|&[middlegame, endgame]| {
+                    if middlegame == endgame {
+                        middlegame.to_string()
+                    } else {
+                        format!("{}/{}", middlegame, endgame)
+                    }
+                });
+            if len == 64 {
+                writeln!(f, "{} =", name)?;
+                let values: Vec<String> = values.collect();
+                for rank in values.chunks(8) {
+                    let rank: Vec<String> =
+                        rank.iter().map(|value| format!("{:>8}", value)).collect();
+                    writeln!(f, "{}", rank.concat())?;
+                }
+            } else {
+                writeln!(
+                    f,
+                    "{} = {}",
+                    name,
+                    values.collect::<Vec<String>>().join(" ")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The features of a position that [`Params::evaluate`] weighs. The
+/// evaluation is linear in the weights, which is what makes tuning them
+/// quick.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    /// Each weight that applies, by its index in [`Params::weights`], with the
+    /// number of times it counts for White less the number of times for Black.
+    pub terms: Vec<(usize, i32)>,
+    /// How much of the middlegame weight to use, from 1.0 with every piece on
+    /// the board down to 0.0 with only kings and pawns.
+    pub phase: f64,
+}
+
+impl Trace {
+    /// The features of `state`.
+    pub fn new(state: &State) -> Trace {
+        let mut terms = Vec::new();
+        let phase = features(state, |index, count| terms.push((index, count)));
+        Trace {
+            terms,
+            phase: phase as f64 / MAX_PHASE as f64,
+        }
+    }
+
+    /// The evaluation from White's point of view with the given weights,
+    /// without rounding.
+    pub fn evaluate(&self, weights: &[[f64; 2]]) -> f64 {
+        let mut score = [0.0, 0.0];
+        for &(index, count) in self.terms.iter() {
+            score[0] += weights[index][0] * count as f64;
+            score[1] += weights[index][1] * count as f64;
+        }
+        score[0] * self.phase + score[1] * (1.0 - self.phase)
+    }
+}
+
+// The name, first weight and number of weights of each parameter.
+fn sections() -> Vec<(String, usize, usize)> {
+    let mut sections = Vec::new();
+    for (i, name) in NAMES.iter().enumerate() {
+        sections.push((format!("value.{}", name), VALUE + i, 1));
+    }
+    for (i, name) in NAMES.iter().enumerate() {
+        sections.push((format!("table.{}", name), TABLE + i * 64, 64));
+    }
+    for (i, name) in NAMES[1..5].iter().enumerate() {
+        sections.push((format!("mobility.{}", name), MOBILITY + i, 1));
+    }
+    for &(name, start, len) in [
+        ("pawn.doubled", DOUBLED_PAWN, 1),
+        ("pawn.isolated", ISOLATED_PAWN, 1),
+        ("pawn.passed", PASSED_PAWN, 8),
+        ("king.shield", PAWN_SHIELD, 1),
+        ("king.attack", KING_ATTACK, 1),
+        ("bishop.pair", BISHOP_PAIR, 1),
+    ]
+    .iter()
+    {
+        sections.push((name.to_string(), start, len));
+    }
+    sections
+}
+
+fn parse_weight(token: &str) -> Option<[i32; 2]> {
+    match token.find('/') {
+        Some(slash) => Some([
+            token[..slash].parse().ok()?,
+            token[slash + 1..].parse().ok()?,
+        ]),
+        None => {
+            let value = token.parse().ok()?;
+            Some([value, value])
+        }
+    }
+}
+
+// Calls `add` with the index of each weight that applies to the position and
+// how many times it does, counting White's as positive and Black's as
+// negative, and returns the phase out of `MAX_PHASE`.
+fn features(state: &State, mut add: impl FnMut(usize, i32)) -> i32 {
+    let board = &state.board;
+    let mut phase = 0;
+    for (pos, piece) in board.iter() {
+        let sign = if piece.colour == White { 1 } else { -1 };
+        let variant = piece.variant as usize;
+        let square = if piece.colour == White {
+            (7 - pos.get_y()) * 8 + pos.get_x()
+        } else {
+            pos.get_y() * 8 + pos.get_x()
+        } as usize;
+        add(VALUE + variant, sign);
+        add(TABLE + variant * 64 + square, sign);
+        phase += PHASES[variant];
+        if let Knight | Bishop | Rook | Queen = piece.variant {
+            let moves = board.attacks(pos, piece) & !board.colour(piece.colour);
+            add(MOBILITY + variant - 1, sign * moves.count_ones() as i32);
+        }
+    }
+
+    for &colour in [White, Black].iter() {
+        let sign = if colour == White { 1 } else { -1 };
+        // Pieces in hand in Crazyhouse count as material.
+        for &variant in [Pawn, Knight, Bishop, Rook, Queen].iter() {
+            let count = state.pocket(colour, variant) as i32;
+            if count > 0 {
+                add(VALUE + variant as usize, sign * count);
+            }
+        }
+        let pawns = board.pieces(colour, Pawn);
+        let enemy_pawns = board.pieces(colour.flipped(), Pawn);
+        for x in 0..8 {
+            let count = (pawns & FILE << x).count_ones() as i32;
+            if count > 1 {
+                add(DOUBLED_PAWN, sign * (count - 1));
+            }
+        }
+        for pos in squares(pawns) {
+            let x = pos.get_x();
+            if pawns & adjacent_files(x) == 0 {
+                add(ISOLATED_PAWN, sign);
+            }
+            let front = (adjacent_files(x) | FILE << x) & ahead(colour, pos.get_y());
+            if enemy_pawns & front == 0 {
+                let rank = if colour == White {
+                    pos.get_y()
+                } else {
+                    7 - pos.get_y()
+                };
+                add(PASSED_PAWN + rank as usize, sign);
+            }
+        }
+
+        if let Some(king) = board.king(colour) {
+            let zone = king_attacks(king);
+            let shield = pawns & zone & ahead(colour, king.get_y());
+            add(PAWN_SHIELD, sign * shield.count_ones() as i32);
+            let attacked = squares(zone)
+                .filter(|&pos| board.is_attacked(pos, colour.flipped()))
+                .count();
+            add(KING_ATTACK, sign * attacked as i32);
+        }
+        if board.pieces(colour, Bishop).count_ones() > 1 {
+            add(BISHOP_PAIR, sign);
+        }
+    }
+    phase.min(MAX_PHASE)
+}
+
+fn adjacent_files(x: i8) -> Bitboard {
+    let mut files = 0;
+    if x > 0 {
+        files |= FILE << (x - 1);
+    }
+    if x < 7 {
+        files |= FILE << (x + 1);
+    }
+    files
+}
+
+// The ranks in front of rank `y` from the point of view of `colour`.
+fn ahead(colour: Colour, y: i8) -> Bitboard {
+    match colour {
+        White if y == 7 => 0,
+        White => !0 << (8 * (y + 1)),
+        Black => (1 << (8 * y)) - 1,
+    }
+}
+
+/// What was wrong with evaluation parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamsErrorKind {
+    /// A key that is not the name of a parameter.
+    UnknownKey(String),
+    /// Values that come before any key.
+    MissingKey,
+    /// A value that is not a number or a pair of numbers.
+    InvalidValue(String),
+    /// A parameter given the wrong number of values.
+    WrongLength {
+        /// The number of values the parameter has.
+        expected: usize,
+        /// The number of values given.
+        found: usize,
+    },
+}
+
+/// An error from [`Params::parse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamsError {
+    /// The line of the error, starting at 1.
+    pub line: usize,
+    /// What the error is.
+    pub kind: ParamsErrorKind,
+}
+
+impl ParamsError {
+    fn new(line: usize, kind: ParamsErrorKind) -> ParamsError {
+        ParamsError { line, kind }
+    }
+}
+
+impl fmt::Display for ParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ParamsErrorKind::UnknownKey(key) => write!(f, "unknown parameter '{}'", key),
+            ParamsErrorKind::MissingKey => write!(f, "values without a parameter name"),
+            ParamsErrorKind::InvalidValue(value) => write!(f, "invalid value '{}'", value),
+            ParamsErrorKind::WrongLength { expected, found } => {
+                write!(
+                    f,
+                    "wrong number of values, expected {} but found {}",
+                    expected, found
+                )
+            }
+        }
+    }
+}
+
+impl Error for ParamsError {}
@@ -0,0 +1,551 @@
+use crate::bitboard::*;
+use crate::state::{castling_right, StateInfo};
+use crate::*;
+
+impl State {
+    /// Parses a position from Forsyth-Edwards Notation.
+    ///
+    /// The halfmove clock and fullmove number may be left out, defaulting to
+    /// 0 and 1. Positions that could not arise in a game, such as a missing
+    /// king or the side not to move being in check, are rejected.
+    pub fn from_fen(fen: &str) -> Result<State, FenError> {
+        State::from_fen_with_rules(fen, &Standard)
+    }
+
+    /// Parses a position in a variant from Forsyth-Edwards Notation. Where
+    /// the rules have a check limit, the checks each side has given may follow
+    /// as a last field such as `+1+0`.
+    pub fn from_fen_with_rules(fen: &str, rules: &'static dyn Rules) -> Result<State, FenError> {
+        let mut fields = Vec::new();
+        let mut index = 0;
+        for field in fen.split(' ') {
+            fields.push((field, index));
+            index += field.chars().count() + 1;
+        }
+        let mut checks = [0; 2];
+        if let (Some(_), Some(&(last, start))) = (rules.check_limit(), fields.last()) {
+            if last.starts_with('+') {
+                checks = parse_checks(last).ok_or_else(|| {
+                    FenError::new(FenField::Checks, start, FenErrorKind::InvalidNumber)
+                })?;
+                fields.pop();
+            }
+        }
+        let field = |n: usize, kind: FenField| -> Result<(&str, usize), FenError> {
+            match fields.get(n) {
+                Some(&(f, i)) if !f.is_empty() => Ok((f, i)),
+                Some(&(_, i)) => Err(FenError::new(kind, i, FenErrorKind::Missing)),
+                None => Err(FenError::new(
+                    kind,
+                    fen.chars().count(),
+                    FenErrorKind::Missing,
+                )),
+            }
+        };
+
+        if fields.len() > 6 {
+            return Err(FenError::new(
+                FenField::FullmoveNumber,
+                fields[6].1,
+                FenErrorKind::TooManyFields,
+            ));
+        }
+
+        // In variants with drops the placement may be followed by the pieces
+        // in hand, as in `[Qp]`, and promoted pieces are marked with a `~`.
+        let (placement, start) = field(0, FenField::Placement)?;
+        let (placement, pocket) = match placement.find('[') {
+            Some(i) if rules.drops() => (&placement[..i], Some(&placement[i..])),
+            _ => (placement, None),
+        };
+        let mut board = Board::empty();
+        let mut promoted = 0;
+        let mut last = None;
+        let mut x = 0;
+        let mut y = 7;
+        for (i, c) in placement.chars().enumerate() {
+            let error = |kind| Err(FenError::new(FenField::Placement, start + i, kind));
+            match c {
+                '~' if rules.drops() => match last.take() {
+                    Some(pos) => promoted |= bit(pos),
+                    None => return error(FenErrorKind::InvalidCharacter(c)),
+                },
+                '/' => {
+                    if x != 8 {
+                        return error(FenErrorKind::BadRankLength);
+                    }
+                    if y == 0 {
+                        return error(FenErrorKind::TooManyRanks);
+                    }
+                    x = 0;
+                    y -= 1;
+                }
+                '1'..='8' => {
+                    x += c as i8 - b'0' as i8;
+                    if x > 8 {
+                        return error(FenErrorKind::BadRankLength);
+                    }
+                }
+                c => {
+                    let piece = match Piece::from_char(c) {
+                        Some(piece) => piece,
+                        None => return error(FenErrorKind::InvalidCharacter(c)),
+                    };
+                    let pos = match Position::from_xy(x, y) {
+                        Some(pos) => pos,
+                        None => return error(FenErrorKind::BadRankLength),
+                    };
+                    let first = if piece.colour == White { 0 } else { 7 };
+                    if piece.variant == Pawn
+                        && (y == 0 || y == 7)
+                        && !(y == first && rules.first_rank_pawns(piece.colour))
+                    {
+                        return error(FenErrorKind::PawnOnBackRank(pos));
+                    }
+                    board.set(pos, piece);
+                    last = Some(pos);
+                    x += 1;
+                    continue;
+                }
+            }
+            last = None;
+        }
+        if x != 8 {
+            return Err(FenError::new(
+                FenField::Placement,
+                start + placement.chars().count(),
+                FenErrorKind::BadRankLength,
+            ));
+        }
+        if y != 0 {
+            return Err(FenError::new(
+                FenField::Placement,
+                start + placement.chars().count(),
+                FenErrorKind::TooFewRanks,
+            ));
+        }
+        let mut pockets = [[0; 5]; 2];
+        if let Some(pocket) = pocket {
+            let start = start + placement.chars().count();
+            let pieces = match pocket.strip_suffix(']') {
+                Some(pieces) => &pieces[1..],
+                None => {
+                    return Err(FenError::new(
+                        FenField::Placement,
+                        start + pocket.chars().count(),
+                        FenErrorKind::Missing,
+                    ))
+                }
+            };
+            for (i, c) in pieces.chars().enumerate() {
+                match Piece::from_char(c) {
+                    Some(piece) if piece.variant != King => {
+                        pockets[piece.colour as usize][piece.variant as usize] += 1
+                    }
+                    _ => {
+                        return Err(FenError::new(
+                            FenField::Placement,
+                            start + 1 + i,
+                            FenErrorKind::InvalidCharacter(c),
+                        ))
+                    }
+                }
+            }
+        }
+        for &colour in [White, Black].iter() {
+            let kings = board.pieces(colour, King).count_ones();
+            if kings != 1 && rules.royal_king(colour) {
+                return Err(FenError::new(
+                    FenField::Placement,
+                    start,
+                    if kings == 0 {
+                        FenErrorKind::MissingKing(colour)
+                    } else {
+                        FenErrorKind::ExtraKing(colour)
+                    },
+                ));
+            }
+        }
+
+        let (turn, start) = field(1, FenField::Turn)?;
+        let turn = match turn {
+            "w" => White,
+            "b" => Black,
+            _ => {
+                return Err(FenError::new(
+                    FenField::Turn,
+                    start,
+                    FenErrorKind::InvalidCharacter(turn.chars().next().unwrap()),
+                ))
+            }
+        };
+
+        // Besides KQkq, which stand for the outermost rook on each side of the
+        // king, the rook's file may be given as in X-FEN and Shredder-FEN,
+        // upper case for white.
+        let (castling, start) = field(2, FenField::Castling)?;
+        let mut info = StateInfo {
+            castling: [false; 4],
+            en_passant: None,
+            promoting: None,
+        };
+        let mut rooks = STANDARD_ROOKS;
+        if castling != "-" {
+            for (i, c) in castling.chars().enumerate() {
+                let colour = if c.is_ascii_uppercase() { White } else { Black };
+                let rank = if colour == White { 0 } else { 7 };
+                let king = match board.king(colour).filter(|_| rules.royal_king(colour)) {
+                    Some(king) => king,
+                    None => {
+                        return Err(FenError::new(
+                            FenField::Castling,
+                            start + i,
+                            FenErrorKind::InvalidCastling(c),
+                        ))
+                    }
+                };
+                let is_rook = |x: i8| {
+                    board.get(Position::from_xy(x, rank).unwrap())
+                        == Some(Piece {
+                            colour,
+                            variant: Rook,
+                        })
+                };
+                let file = match c.to_ascii_lowercase() {
+                    'k' => (king.get_x() + 1..8).rev().find(|&x| is_rook(x)),
+                    'q' => (0..king.get_x()).find(|&x| is_rook(x)),
+                    file @ 'a'..='h' => Some(file as i8 - b'a' as i8).filter(|&x| is_rook(x)),
+                    _ => {
+                        return Err(FenError::new(
+                            FenField::Castling,
+                            start + i,
+                            FenErrorKind::InvalidCharacter(c),
+                        ))
+                    }
+                };
+                let right = file.filter(|_| king.get_y() == rank).map(|x| {
+                    let side = if x > king.get_x() { 0 } else { 1 };
+                    (
+                        castling_right(colour, side),
+                        Position::from_xy(x, rank).unwrap(),
+                    )
+                });
+                match right {
+                    Some((right, rook)) if !info.castling[right] => {
+                        info.castling[right] = true;
+                        rooks[right] = rook;
+                    }
+                    _ => {
+                        return Err(FenError::new(
+                            FenField::Castling,
+                            start + i,
+                            FenErrorKind::InvalidCastling(c),
+                        ))
+                    }
+                }
+            }
+        }
+        let chess960 = (0..4).filter(|&right| info.castling[right]).any(|right| {
+            let king = board.king(if right < 2 { White } else { Black }).unwrap();
+            king.get_x() != 4 || rooks[right] != STANDARD_ROOKS[right]
+        });
+
+        let (en_passant, start) = field(3, FenField::EnPassant)?;
+        if en_passant != "-" {
+            let error = Err(FenError::new(
+                FenField::EnPassant,
+                start,
+                FenErrorKind::InvalidEnPassant,
+            ));
+            let pos = match Position::parse(en_passant) {
+                Some(pos) => pos,
+                None => return error,
+            };
+            let (rank, pawn) = if turn == White { (5, -1) } else { (2, 1) };
+            if pos.get_y() != rank
+                || board.get(pos).is_some()
+                || board.get(pos.offset_by(0, pawn).unwrap())
+                    != Some(Piece {
+                        colour: turn.flipped(),
+                        variant: Pawn,
+                    })
+            {
+                return error;
+            }
+            info.en_passant = Some(pos);
+        }
+
+        let halfmove_clock = match fields.get(4) {
+            Some(&(clock, start)) => clock.parse::<u32>().map_err(|_| {
+                FenError::new(FenField::HalfmoveClock, start, FenErrorKind::InvalidNumber)
+            })?,
+            None => 0,
+        };
+        let fullmove_number = match fields.get(5) {
+            Some(&(number, start)) => match number.parse::<u32>() {
+                Ok(n) if n > 0 => n,
+                _ => {
+                    return Err(FenError::new(
+                        FenField::FullmoveNumber,
+                        start,
+                        FenErrorKind::InvalidNumber,
+                    ))
+                }
+            },
+            None => 1,
+        };
+
+        let mut state = State {
+            board,
+            moves: Vec::new(),
+            turn,
+            info,
+            rooks,
+            chess960,
+            rules,
+            checks,
+            pockets,
+            promoted,
+            halfmove_clock,
+            fullmove_number,
+            hash: 0,
+            ended: None,
+        };
+        if state.exposed(&state.board, turn.flipped()) {
+            return Err(FenError::new(
+                FenField::Turn,
+                fields[1].1,
+                FenErrorKind::OpponentInCheck,
+            ));
+        }
+        state.update_hash();
+        state.gen_legal_moves();
+        Ok(state)
+    }
+
+    /// Writes the position in Forsyth-Edwards Notation.
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for y in (0..8).rev() {
+            let mut empty = 0;
+            for x in 0..8 {
+                let pos = Position::from_xy(x, y).unwrap();
+                if let Some(piece) = self.board.get(pos) {
+                    if empty > 0 {
+                        fen.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    fen.push(piece.to_char());
+                    if self.promoted & bit(pos) != 0 {
+                        fen.push('~');
+                    }
+                } else {
+                    empty += 1;
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if y > 0 {
+                fen.push('/');
+            }
+        }
+        if self.rules.drops() {
+            fen.push('[');
+            for &colour in [White, Black].iter() {
+                for &variant in [Queen, Rook, Bishop, Knight, Pawn].iter() {
+                    let piece = Piece { colour, variant };
+                    for _ in 0..self.pocket(colour, variant) {
+                        fen.push(piece.to_char());
+                    }
+                }
+            }
+            fen.push(']');
+        }
+
+        fen.push(' ');
+        fen.push(if self.turn == White { 'w' } else { 'b' });
+
+        fen.push(' ');
+        let castling_start = fen.len();
+        for (right, &rook) in self.rooks.iter().enumerate() {
+            if self.info.castling[right] {
+                fen.push(self.castling_char(right, rook));
+            }
+        }
+        if fen.len() == castling_start {
+            fen.push('-');
+        }
+
+        fen.push(' ');
+        match self.info.en_passant {
+            Some(pos) => fen.push_str(&pos.to_string()),
+            None => fen.push('-'),
+        }
+
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock, self.fullmove_number
+        ));
+        if self.rules.check_limit().is_some() {
+            fen.push_str(&format!(" +{}+{}", self.checks[0], self.checks[1]));
+        }
+        fen
+    }
+
+    // KQkq when the rook is the outermost on its side of the king, as in
+    // X-FEN, and otherwise the rook's file.
+    fn castling_char(&self, right: usize, rook: Position) -> char {
+        let colour = if right < 2 { White } else { Black };
+        let (beyond, side) = match right % 2 {
+            0 => (rook.get_x() + 1..8, 'k'),
+            _ => (0..rook.get_x(), 'q'),
+        };
+        let outermost = beyond.into_iter().all(|x| {
+            self.board.get(Position::from_xy(x, rook.get_y()).unwrap())
+                != Some(Piece {
+                    colour,
+                    variant: Rook,
+                })
+        });
+        let c = if outermost {
+            side
+        } else {
+            (b'a' + rook.get_x() as u8) as char
+        };
+        if colour == White {
+            c.to_ascii_uppercase()
+        } else {
+            c
+        }
+    }
+}
+
+// Reads the checks given by each side, written as `+W+B`.
+fn parse_checks(field: &str) -> Option<[u32; 2]> {
+    let mut counts = field[1..].split('+').map(|count| count.parse::<u32>().ok());
+    match (counts.next()?, counts.next()?, counts.next()) {
+        (Some(white), Some(black), None) => Some([white, black]),
+        _ => None,
+    }
+}
+
+// The rook squares of the standard starting position, by castling right.
+const STANDARD_ROOKS: [Position; 4] = [
+    Position { value: 7 },
+    Position { value: 0 },
+    Position { value: 63 },
+    Position { value: 56 },
+];
+
+/// The space-separated fields of a FEN string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FenField {
+    /// The piece placement, rank 8 first.
+    Placement,
+    /// The side to move, `w` or `b`.
+    Turn,
+    /// The castling rights, e.g. `KQkq` or `-`.
+    Castling,
+    /// The en passant target square, or `-`.
+    EnPassant,
+    /// Halfmoves since the last capture or pawn move.
+    HalfmoveClock,
+    /// The number of the current full move, starting at 1.
+    FullmoveNumber,
+    /// The checks given by each side, in variants with a check limit.
+    Checks,
+}
+
+/// What was wrong with a FEN string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FenErrorKind {
+    /// The field is empty or absent.
+    Missing,
+    /// There are more than six fields.
+    TooManyFields,
+    /// A character that is not allowed in the field.
+    InvalidCharacter(char),
+    /// A rank does not describe exactly eight squares.
+    BadRankLength,
+    /// The placement has fewer than eight ranks.
+    TooFewRanks,
+    /// The placement has more than eight ranks.
+    TooManyRanks,
+    /// The given side has no king.
+    MissingKing(Colour),
+    /// The given side has more than one king.
+    ExtraKing(Colour),
+    /// A pawn stands on the first or eighth rank.
+    PawnOnBackRank(Position),
+    /// The side not to move is in check.
+    OpponentInCheck,
+    /// A castling right without a rook to castle with, or given twice.
+    InvalidCastling(char),
+    /// The en passant square is not behind a pawn that just made a double push.
+    InvalidEnPassant,
+    /// A clock is not a valid number.
+    InvalidNumber,
+}
+
+/// An error from [`State::from_fen`], locating the problem in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FenError {
+    /// The field containing the error.
+    pub field: FenField,
+    /// The character index of the error in the input.
+    pub index: usize,
+    /// What the error is.
+    pub kind: FenErrorKind,
+}
+
+impl FenError {
+    fn new(field: FenField, index: usize, kind: FenErrorKind) -> FenError {
+        FenError { field, index, kind }
+    }
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let field = match self.field {
+            FenField::Placement => "piece placement",
+            FenField::Turn => "side to move",
+            FenField::Castling => "castling rights",
+            FenField::EnPassant => "en passant square",
+            FenField::HalfmoveClock => "halfmove clock",
+            FenField::FullmoveNumber => "fullmove number",
+            FenField::Checks => "checks given",
+        };
+        write!(
+            f,
+            "invalid {} at character {}: {}",
+            field, self.index, self.kind
+        )
+    }
+}
+
+impl fmt::Display for FenErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FenErrorKind::Missing => write!(f, "field is missing"),
+            FenErrorKind::TooManyFields => write!(f, "unexpected extra field"),
+            FenErrorKind::InvalidCharacter(c) => write!(f, "unexpected character '{}'", c),
+            FenErrorKind::BadRankLength => write!(f, "rank does not have 8 squares"),
+            FenErrorKind::TooFewRanks => write!(f, "fewer than 8 ranks"),
+            FenErrorKind::TooManyRanks => write!(f, "more than 8 ranks"),
+            FenErrorKind::MissingKing(colour) => write!(f, "{} has no king", colour),
+            FenErrorKind::ExtraKing(colour) => write!(f, "{} has more than one king", colour),
+            FenErrorKind::PawnOnBackRank(pos) => write!(f, "pawn on back rank at {}", pos),
+            FenErrorKind::OpponentInCheck => write!(f, "side not to move is in check"),
+            FenErrorKind::InvalidCastling(c) => write!(
+                f,
+                "castling right '{}' does not match king and rook squares",
+                c
+            ),
+            FenErrorKind::InvalidEnPassant => write!(f, "not a valid en passant square"),
+            FenErrorKind::InvalidNumber => write!(f, "not a valid number"),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
@@ -0,0 +1,301 @@
+//! Rules of chess: move generation, game end detection and the standard
+//! notations (FEN, SAN and PGN), along with a [`Searcher`] that finds good
+//! moves for playing against the computer and for analysis, guided by an
+//! evaluation whose weights are loaded as [`Params`]. Other engines can be
+//! run and spoken to over UCI with an [`Engine`].
+//!
+//! The central type is [`State`], a complete position that knows its own legal
+//! moves. Positions are created with [`State::new`], [`State::from_fen`] or
+//! [`State::chess960`], or for other variants with [`State::with_rules`] and
+//! one of the [`RULES`], and moves are played either as a whole [`Move`] with
+//! [`State::apply`], or in two steps with [`State::try_move`] followed by
+//! [`State::promote`] when a pawn reaches the last rank.
+//!
+//! ```
+//! use chess_core::State;
+//!
+//! let mut state = State::new();
+//! let mv = state.parse_san("e4").unwrap();
+//! assert!(state.apply(mv));
+//! assert_eq!(
+//!     state.to_fen(),
+//!     "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+//! );
+//! ```
+
+#![warn(missing_docs)]
+
+mod bitboard;
+mod chess960;
+mod clock;
+mod eval;
+mod fen;
+mod pgn;
+mod rules;
+mod san;
+mod search;
+mod state;
+mod uci;
+mod zobrist;
+
+pub use clock::{Bonus, Clock, Stage, TimeControl};
+pub use eval::{Params, ParamsError, ParamsErrorKind, Trace};
+pub use fen::{FenError, FenErrorKind, FenField};
+pub use pgn::{Game, Node, PgnError, PgnErrorKind};
+pub use rules::{
+    find_rules, Antichess, Atomic, Crazyhouse, Horde, KingOfTheHill, RacingKings, Rules, Standard,
+    ThreeCheck, RULES,
+};
+pub use san::SanError;
+pub use search::{Limits, Score, SearchResult, Searcher};
+pub use state::{State, Undo};
+pub use uci::{Engine, EngineEvent, EngineOption, Go, Info, OptionKind, UciError};
+
+use std::fmt;
+use Colour::*;
+use Variant::*;
+
+/// How a finished game ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EndState {
+    /// The given colour delivered checkmate.
+    Checkmate(Colour),
+    /// The side to move has no legal moves but is not in check.
+    Stalemate,
+    /// Neither side has enough material to checkmate.
+    InsufficientMaterial,
+    /// The same position occurred three times.
+    ThreefoldRepetition,
+    /// A player claimed a draw after fifty moves without a capture or pawn move.
+    FiftyMoveRule,
+    /// Seventy-five moves passed without a capture or pawn move.
+    SeventyFiveMoveRule,
+    /// The given colour won because the opponent ran out of time.
+    Timeout(Colour),
+    /// A player ran out of time, but the opponent could not have checkmated.
+    TimeoutVsInsufficientMaterial,
+    /// The given colour won by the variant's own rules.
+    VariantWin(Colour),
+    /// The game was drawn by the variant's own rules.
+    VariantDraw,
+}
+
+impl EndState {
+    /// The colour that won, or `None` for a draw.
+    pub fn winner(&self) -> Option<Colour> {
+        match *self {
+            EndState::Checkmate(colour)
+            | EndState::Timeout(colour)
+            | EndState::VariantWin(colour) => Some(colour),
+            _ => None,
+        }
+    }
+}
+
+/// A fully specified legal move, as returned by [`State::legal_moves`].
+///
+/// Displays in UCI long algebraic notation, e.g. `e7e8q`, or `N@f3` for a
+/// drop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move {
+    /// The square the piece moves from.
+    pub from: Position,
+    /// The square the piece moves to. For castling this is the king's
+    /// destination, or in Chess960 the square of the rook it castles with.
+    pub to: Position,
+    /// The piece a pawn promotes to, if any. In Atomic a pawn that promotes
+    /// by capturing is blown up, so the promoted piece never reaches the
+    /// board.
+    pub promotion: Option<Variant>,
+    /// The piece captured by this move, if any.
+    pub captured: Option<Piece>,
+    /// What sort of move this is.
+    pub kind: MoveKind,
+}
+
+/// Distinguishes moves with side effects beyond moving one piece.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MoveKind {
+    /// An ordinary move or capture, including promotions.
+    Normal,
+    /// A pawn moving two squares from its starting rank.
+    DoublePush,
+    /// A pawn capturing en passant.
+    EnPassant,
+    /// The king castling, with the rook moving alongside it.
+    Castle,
+    /// A piece of the given variant dropped from the pocket, in variants with
+    /// drops. The move is then from and to the square it lands on.
+    Drop(Variant),
+}
+
+/// A square on the board, from `a1` at (0, 0) to `h8` at (7, 7).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Position {
+    value: u8,
+}
+
+impl Position {
+    /// Returns the square at file `x` and rank `y`, or `None` if off the board.
+    pub fn from_xy(x: i8, y: i8) -> Option<Position> {
+        if x >= 0 && y >= 0 && x < 8 && y < 8 {
+            return Some(Position {
+                value: ((y as u8) << 3) | (x as u8),
+            });
+        }
+        None
+    }
+
+    /// Parses a square name such as `e4`.
+    pub fn parse(name: &str) -> Option<Position> {
+        let mut chars = name.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(file @ 'a'..='h'), Some(rank @ '1'..='8'), None) => {
+                Position::from_xy(file as i8 - 'a' as i8, rank as i8 - '1' as i8)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the square offset from this one, or `None` if off the board.
+    pub fn offset_by(&self, x: i8, y: i8) -> Option<Position> {
+        Position::from_xy((self.value & 0b111) as i8 + x, (self.value >> 3) as i8 + y)
+    }
+
+    /// The file, from 0 (a) to 7 (h).
+    pub fn get_x(&self) -> i8 {
+        (self.value & 0b111) as i8
+    }
+
+    /// The rank, from 0 (1) to 7 (8).
+    pub fn get_y(&self) -> i8 {
+        (self.value >> 3) as i8
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let MoveKind::Drop(variant) = self.kind {
+            return write!(f, "{}@{}", variant.to_char(), self.to);
+        }
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(variant) = self.promotion {
+            write!(f, "{}", variant.to_char().to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            (b'a' + self.get_x() as u8) as char,
+            self.get_y() + 1
+        )
+    }
+}
+
+/// A piece of a given colour.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Piece {
+    /// Which side the piece belongs to.
+    pub colour: Colour,
+    /// What kind of piece it is.
+    pub variant: Variant,
+}
+
+impl Piece {
+    /// Parses a FEN piece letter: uppercase for White, lowercase for Black.
+    pub fn from_char(c: char) -> Option<Piece> {
+        Some(Piece {
+            colour: if c.is_ascii_uppercase() { White } else { Black },
+            variant: Variant::from_char(c.to_ascii_uppercase())?,
+        })
+    }
+
+    /// The FEN piece letter: uppercase for White, lowercase for Black.
+    pub fn to_char(&self) -> char {
+        let c = self.variant.to_char();
+        if self.colour == White {
+            c
+        } else {
+            c.to_ascii_lowercase()
+        }
+    }
+}
+
+/// One of the two sides.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Colour {
+    /// The side that moves first.
+    White,
+    /// The side that moves second.
+    Black,
+}
+
+impl Colour {
+    fn flip(&mut self) {
+        *self = if self == &White { Black } else { White };
+    }
+
+    /// The other side.
+    pub fn flipped(&self) -> Colour {
+        if self == &White {
+            Black
+        } else {
+            White
+        }
+    }
+}
+
+impl fmt::Display for Colour {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", if self == &White { "White" } else { "Black" })
+    }
+}
+
+/// The kind of a piece.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Variant {
+    /// A pawn.
+    Pawn,
+    /// A knight.
+    Knight,
+    /// A bishop.
+    Bishop,
+    /// A rook.
+    Rook,
+    /// A queen.
+    Queen,
+    /// A king.
+    King,
+}
+
+impl Variant {
+    /// Parses an uppercase piece letter such as `N`.
+    pub fn from_char(c: char) -> Option<Variant> {
+        match c {
+            'P' => Some(Pawn),
+            'N' => Some(Knight),
+            'B' => Some(Bishop),
+            'R' => Some(Rook),
+            'Q' => Some(Queen),
+            'K' => Some(King),
+            _ => None,
+        }
+    }
+
+    /// The uppercase piece letter.
+    pub fn to_char(&self) -> char {
+        match self {
+            Pawn => 'P',
+            Knight => 'N',
+            Bishop => 'B',
+            Rook => 'R',
+            Queen => 'Q',
+            King => 'K',
+        }
+    }
+}
@@ -0,0 +1,623 @@
+use crate::*;
+use std::error::Error;
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+const SUFFIXES: [&str; 6] = ["!", "?", "!!", "??", "!?", "?!"];
+const LINE_LENGTH: usize = 79;
+
+/// A recorded game: its tags, starting position and moves, read from or
+/// written as Portable Game Notation.
+///
+/// ```
+/// use chess_core::{Game, Node, State};
+///
+/// let mut game = Game::new(State::new());
+/// game.set_tag("White", "Carlsen, Magnus");
+/// let mut state = State::new();
+/// for san in ["e4", "e5"].iter() {
+///     let mv = state.parse_san(san).unwrap();
+///     state.apply(mv);
+///     game.moves.push(Node::new(mv));
+/// }
+/// let pgn = game.to_pgn().unwrap();
+/// assert!(pgn.ends_with("1. e4 e5 *\n"));
+/// assert_eq!(Game::from_pgn(&pgn).unwrap().to_pgn(), Some(pgn));
+/// ```
+#[derive(Clone)]
+pub struct Game {
+    /// Tag pairs in the order they are written. [`Game::new`] fills in the
+    /// Seven Tag Roster.
+    pub tags: Vec<(String, String)>,
+    /// The position before the first move.
+    pub start: State,
+    /// The main line, played from `start`.
+    pub moves: Vec<Node>,
+    /// How the game ended, if it has. This takes precedence over the
+    /// `Result` tag when writing the game.
+    pub ended: Option<EndState>,
+}
+
+/// A move in a game, with its annotations and the alternatives to it.
+#[derive(Clone)]
+pub struct Node {
+    /// The move played.
+    pub mv: Move,
+    /// Numeric Annotation Glyphs, such as 1 for `!` or 4 for `??`.
+    pub nags: Vec<u8>,
+    /// A comment written before the move.
+    pub comment_before: Option<String>,
+    /// A comment written after the move.
+    pub comment: Option<String>,
+    /// Lines that could have been played instead of this move, each starting
+    /// from the same position as it.
+    pub variations: Vec<Vec<Node>>,
+}
+
+impl Node {
+    /// A move with no annotations or variations.
+    pub fn new(mv: Move) -> Node {
+        Node {
+            mv,
+            nags: Vec::new(),
+            comment_before: None,
+            comment: None,
+            variations: Vec::new(),
+        }
+    }
+}
+
+impl Game {
+    /// A game with no moves, tagged with the Seven Tag Roster and unknown
+    /// values.
+    pub fn new(start: State) -> Game {
+        let tags = [
+            ("Event", "?"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "?"),
+            ("White", "?"),
+            ("Black", "?"),
+            ("Result", "*"),
+        ];
+        Game {
+            tags: tags
+                .iter()
+                .map(|&(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            start,
+            moves: Vec::new(),
+            ended: None,
+        }
+    }
+
+    /// Reads the first game from PGN text.
+    pub fn from_pgn(pgn: &str) -> Result<Game, PgnError> {
+        let mut games = Game::parse_all(pgn)?;
+        if games.is_empty() {
+            let (line, column) = end_of(pgn);
+            return Err(PgnError::new(line, column, PgnErrorKind::NoGame));
+        }
+        Ok(games.swap_remove(0))
+    }
+
+    /// Reads every game from PGN text, such as a tournament file.
+    pub fn parse_all(pgn: &str) -> Result<Vec<Game>, PgnError> {
+        Parser::new(pgn).parse()
+    }
+
+    /// The value of a tag, if present.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets a tag, replacing its value if it is already present.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// The game termination marker: `1-0`, `0-1`, `1/2-1/2` or `*`. Taken
+    /// from `ended` if set, and otherwise from the `Result` tag.
+    pub fn result(&self) -> &'static str {
+        match &self.ended {
+            Some(end) => match end.winner() {
+                Some(White) => "1-0",
+                Some(Black) => "0-1",
+                None => "1/2-1/2",
+            },
+            None => {
+                let tag = self.tag("Result").unwrap_or("*");
+                RESULTS.iter().find(|&&r| r == tag).unwrap_or(&"*")
+            }
+        }
+    }
+
+    /// The position at the end of the main line, or `None` if one of its
+    /// moves is not legal.
+    pub fn end_state(&self) -> Option<State> {
+        let mut state = self.start.clone();
+        for node in self.moves.iter() {
+            if !state.apply(node.mv) {
+                return None;
+            }
+        }
+        Some(state)
+    }
+
+    /// Writes the game as PGN. `Result`, `SetUp` and `FEN` are filled in from
+    /// the game itself, as is `Variant` for Chess960 and the other variants.
+    /// Returns `None` if one of the moves is not legal.
+    pub fn to_pgn(&self) -> Option<String> {
+        let mut tags = self.tags.clone();
+        tags.retain(|(name, _)| name != "SetUp" && name != "FEN");
+        let result = self.result();
+        match tags.iter_mut().find(|(name, _)| name == "Result") {
+            Some(tag) => tag.1 = result.to_string(),
+            None => tags.push(("Result".to_string(), result.to_string())),
+        }
+        let fen = self.start.to_fen();
+        let rules = self.start.rules();
+        if fen != rules.start_fen() {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), fen));
+        }
+        if self.tag("Variant").is_none() {
+            if rules.name() != Standard.name() {
+                tags.push(("Variant".to_string(), rules.name().to_string()));
+            } else if self.start.is_chess960() {
+                tags.push(("Variant".to_string(), "Chess960".to_string()));
+            }
+        }
+
+        let mut pgn = String::new();
+        for (name, value) in tags.iter() {
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, escape(value)));
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        write_line(&self.start, &self.moves, &mut tokens)?;
+        tokens.push(result.to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_LENGTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+        Some(pgn)
+    }
+}
+
+fn write_line(start: &State, nodes: &[Node], tokens: &mut Vec<String>) -> Option<()> {
+    let mut state = start.clone();
+    let mut number = true;
+    for node in nodes {
+        if let Some(comment) = &node.comment_before {
+            write_comment(comment, tokens);
+            number = true;
+        }
+        if state.get_turn() == White {
+            tokens.push(format!("{}.", state.fullmove_number()));
+        } else if number {
+            tokens.push(format!("{}...", state.fullmove_number()));
+        }
+        tokens.push(state.to_san(node.mv)?);
+        for nag in node.nags.iter() {
+            tokens.push(format!("${}", nag));
+        }
+        number = false;
+        if let Some(comment) = &node.comment {
+            write_comment(comment, tokens);
+            number = true;
+        }
+        for variation in node.variations.iter() {
+            let mut variation_tokens = Vec::new();
+            write_line(&state, variation, &mut variation_tokens)?;
+            if let Some(first) = variation_tokens.first_mut() {
+                first.insert(0, '(');
+                variation_tokens.last_mut().unwrap().push(')');
+                tokens.append(&mut variation_tokens);
+                number = true;
+            }
+        }
+        state.apply(node.mv);
+    }
+    Some(())
+}
+
+// Comments are split into words so that long ones wrap with the movetext.
+fn write_comment(comment: &str, tokens: &mut Vec<String>) {
+    let start = tokens.len();
+    tokens.extend(
+        comment
+            .split_whitespace()
+            .map(|word| word.replace('}', ")")),
+    );
+    if tokens.len() == start {
+        tokens.push(String::new());
+    }
+    tokens[start].insert(0, '{');
+    tokens.last_mut().unwrap().push('}');
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn end_of(text: &str) -> (usize, usize) {
+    let line = text.split('\n').count();
+    let column = text.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (line, column)
+}
+
+struct Line {
+    nodes: Vec<Node>,
+    state: State,
+    before: State,
+    comment: Option<String>,
+}
+
+impl Line {
+    fn new(state: State) -> Line {
+        Line {
+            nodes: Vec::new(),
+            before: state.clone(),
+            state,
+            comment: None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(pgn: &'a str) -> Parser<'a> {
+        Parser {
+            chars: pgn.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn parse(&mut self) -> Result<Vec<Game>, PgnError> {
+        let mut games = Vec::new();
+        while let Some(game) = self.parse_game()? {
+            games.push(game);
+        }
+        Ok(games)
+    }
+
+    fn parse_game(&mut self) -> Result<Option<Game>, PgnError> {
+        let mut game = Game::new(State::new());
+        game.tags.clear();
+        let mut lines: Vec<Line> = Vec::new();
+        let mut found = false;
+
+        loop {
+            self.skip_whitespace();
+            let (line, column) = (self.line, self.column);
+            let c = match self.chars.peek() {
+                Some(&c) => c,
+                None => break,
+            };
+            if c == '[' {
+                if !lines.is_empty() {
+                    break;
+                }
+                self.parse_tag(&mut game)?;
+                found = true;
+                continue;
+            }
+            if lines.is_empty() {
+                game.start = start(&game, line, column)?;
+                lines.push(Line::new(game.start.clone()));
+            }
+            found = true;
+
+            match c {
+                '{' => {
+                    self.next();
+                    let mut comment = String::new();
+                    loop {
+                        match self.next() {
+                            Some('}') => break,
+                            Some(c) => comment.push(c),
+                            None => {
+                                return Err(PgnError::new(
+                                    line,
+                                    column,
+                                    PgnErrorKind::UnterminatedComment,
+                                ))
+                            }
+                        }
+                    }
+                    add_comment(lines.last_mut().unwrap(), &comment);
+                }
+                ';' => {
+                    self.next();
+                    let mut comment = String::new();
+                    while let Some(&c) = self.chars.peek() {
+                        if c == '\n' {
+                            break;
+                        }
+                        comment.push(c);
+                        self.next();
+                    }
+                    add_comment(lines.last_mut().unwrap(), &comment);
+                }
+                '(' => {
+                    self.next();
+                    let top = lines.last().unwrap();
+                    if top.nodes.is_empty() {
+                        return Err(PgnError::new(line, column, PgnErrorKind::Unexpected('(')));
+                    }
+                    let before = top.before.clone();
+                    lines.push(Line::new(before));
+                }
+                ')' => {
+                    self.next();
+                    if lines.len() < 2 {
+                        return Err(PgnError::new(line, column, PgnErrorKind::Unexpected(')')));
+                    }
+                    let variation = lines.pop().unwrap();
+                    let top = lines.last_mut().unwrap();
+                    if !variation.nodes.is_empty() {
+                        top.nodes
+                            .last_mut()
+                            .unwrap()
+                            .variations
+                            .push(variation.nodes);
+                    }
+                }
+                '$' => {
+                    self.next();
+                    let digits = self.take_while(|c| c.is_ascii_digit());
+                    let nag = digits
+                        .parse()
+                        .map_err(|_| PgnError::new(line, column, PgnErrorKind::InvalidNag))?;
+                    match lines.last_mut().unwrap().nodes.last_mut() {
+                        Some(node) => node.nags.push(nag),
+                        None => {
+                            return Err(PgnError::new(line, column, PgnErrorKind::Unexpected('$')))
+                        }
+                    }
+                }
+                '!' | '?' => {
+                    let suffix = self.take_while(|c| c == '!' || c == '?');
+                    let nag = SUFFIXES
+                        .iter()
+                        .position(|&s| s == suffix)
+                        .ok_or_else(|| PgnError::new(line, column, PgnErrorKind::InvalidNag))?;
+                    match lines.last_mut().unwrap().nodes.last_mut() {
+                        Some(node) => node.nags.push(nag as u8 + 1),
+                        None => {
+                            return Err(PgnError::new(line, column, PgnErrorKind::Unexpected(c)))
+                        }
+                    }
+                }
+                '.' => {
+                    self.next();
+                }
+                c if c.is_ascii_alphanumeric() || c == '*' || c == '@' => {
+                    let token =
+                        self.take_while(|c| c.is_ascii_alphanumeric() || "_+#=:-/*@".contains(c));
+                    if RESULTS.contains(&token.as_str()) {
+                        if lines.len() > 1 {
+                            return Err(PgnError::new(
+                                line,
+                                column,
+                                PgnErrorKind::UnterminatedVariation,
+                            ));
+                        }
+                        if game.tag("Result").is_none() {
+                            game.set_tag("Result", &token);
+                        }
+                        break;
+                    }
+                    if token.chars().all(|c| c.is_ascii_digit()) {
+                        continue;
+                    }
+                    let top = lines.last_mut().unwrap();
+                    let mv = top.state.parse_san(&token).map_err(|e| {
+                        PgnError::new(line, column, PgnErrorKind::InvalidMove(token.clone(), e))
+                    })?;
+                    let mut node = Node::new(mv);
+                    node.comment_before = top.comment.take();
+                    top.before = top.state.clone();
+                    top.state.apply(mv);
+                    top.nodes.push(node);
+                }
+                c => return Err(PgnError::new(line, column, PgnErrorKind::Unexpected(c))),
+            }
+        }
+
+        if !found {
+            return Ok(None);
+        }
+        if lines.len() > 1 {
+            return Err(PgnError::new(
+                self.line,
+                self.column,
+                PgnErrorKind::UnterminatedVariation,
+            ));
+        }
+        match lines.pop() {
+            Some(main) => {
+                game.ended = main.state.ended().clone();
+                game.moves = main.nodes;
+            }
+            None => game.start = start(&game, self.line, self.column)?,
+        }
+        Ok(Some(game))
+    }
+
+    fn parse_tag(&mut self, game: &mut Game) -> Result<(), PgnError> {
+        let (line, column) = (self.line, self.column);
+        let error = || PgnError::new(line, column, PgnErrorKind::InvalidTag);
+        self.next();
+        self.skip_whitespace();
+        let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+        self.skip_whitespace();
+        if name.is_empty() || self.next() != Some('"') {
+            return Err(error());
+        }
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some('"') => break,
+                Some('\\') => match self.next() {
+                    Some(c) => value.push(c),
+                    None => return Err(error()),
+                },
+                Some('\n') | None => return Err(error()),
+                Some(c) => value.push(c),
+            }
+        }
+        self.skip_whitespace();
+        if self.next() != Some(']') {
+            return Err(error());
+        }
+        game.set_tag(&name, &value);
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c == '%' && self.column == 1 {
+                while matches!(self.chars.peek(), Some(&c) if c != '\n') {
+                    self.next();
+                }
+            } else if c.is_whitespace() {
+                self.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String {
+        let mut token = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !f(c) {
+                break;
+            }
+            token.push(c);
+            self.next();
+        }
+        token
+    }
+}
+
+fn start(game: &Game, line: usize, column: usize) -> Result<State, PgnError> {
+    let rules = match game.tag("Variant") {
+        Some(name) => find_rules(name).ok_or_else(|| {
+            PgnError::new(line, column, PgnErrorKind::UnknownVariant(name.to_string()))
+        })?,
+        None => &Standard,
+    };
+    match game.tag("FEN") {
+        Some(fen) => State::from_fen_with_rules(fen, rules)
+            .map_err(|e| PgnError::new(line, column, PgnErrorKind::InvalidFen(e))),
+        None => Ok(State::with_rules(rules)),
+    }
+}
+
+fn add_comment(line: &mut Line, comment: &str) {
+    let comment = comment.split_whitespace().collect::<Vec<_>>().join(" ");
+    let target = match line.nodes.last_mut() {
+        Some(node) if line.comment.is_none() => &mut node.comment,
+        _ => &mut line.comment,
+    };
+    *target = match target.take() {
+        Some(existing) => Some(format!("{} {}", existing, comment)),
+        None => Some(comment),
+    };
+}
+
+/// What was wrong with a PGN file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgnErrorKind {
+    /// The text contains no game.
+    NoGame,
+    /// A tag pair is not of the form `[Name "value"]`.
+    InvalidTag,
+    /// The `FEN` tag is not a valid position.
+    InvalidFen(FenError),
+    /// The `Variant` tag names a variant with no [`Rules`].
+    UnknownVariant(String),
+    /// A move could not be read or played.
+    InvalidMove(String, SanError),
+    /// A Numeric Annotation Glyph or move suffix is not valid.
+    InvalidNag,
+    /// A comment is missing its closing brace.
+    UnterminatedComment,
+    /// A variation is missing its closing parenthesis.
+    UnterminatedVariation,
+    /// A character that does not belong where it appears.
+    Unexpected(char),
+}
+
+/// An error from reading PGN, locating the problem in the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnError {
+    /// The line of the error, starting at 1.
+    pub line: usize,
+    /// The column of the error in characters, starting at 1.
+    pub column: usize,
+    /// What the error is.
+    pub kind: PgnErrorKind,
+}
+
+impl PgnError {
+    fn new(line: usize, column: usize, kind: PgnErrorKind) -> PgnError {
+        PgnError { line, column, kind }
+    }
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            PgnErrorKind::NoGame => write!(f, "no game found"),
+            PgnErrorKind::InvalidTag => write!(f, "invalid tag pair"),
+            PgnErrorKind::InvalidFen(e) => write!(f, "invalid FEN tag: {}", e),
+            PgnErrorKind::UnknownVariant(name) => write!(f, "unknown variant '{}'", name),
+            PgnErrorKind::InvalidMove(san, e) => write!(f, "{} '{}'", e, san),
+            PgnErrorKind::InvalidNag => write!(f, "invalid annotation"),
+            PgnErrorKind::UnterminatedComment => write!(f, "comment is never closed"),
+            PgnErrorKind::UnterminatedVariation => write!(f, "variation is never closed"),
+            PgnErrorKind::Unexpected(c) => write!(f, "unexpected '{}'", c),
+        }
+    }
+}
+
+impl Error for PgnError {}
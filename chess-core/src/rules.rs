@@ -0,0 +1,352 @@
+use crate::bitboard::*;
+use crate::*;
+
+const STANDARD_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const CENTRE: Bitboard = 0x0000_0018_1800_0000;
+const EIGHTH_RANK: Bitboard = 0xff00_0000_0000_0000;
+
+/// Every variant that comes with the crate, standard chess first.
+pub const RULES: [&dyn Rules; 8] = [
+    &Standard,
+    &KingOfTheHill,
+    &ThreeCheck,
+    &Atomic,
+    &Antichess,
+    &Horde,
+    &RacingKings,
+    &Crazyhouse,
+];
+
+/// The rules of a chess variant, for positions created with
+/// [`State::with_rules`] or [`State::from_fen_with_rules`].
+///
+/// Every method has a default that gives standard chess, so a variant only
+/// overrides where it differs. Positions generate their own moves, guided by
+/// the switches here, while the remaining methods decide how the game ends.
+pub trait Rules: Send + Sync {
+    /// The variant's name, as written in a PGN `Variant` tag.
+    fn name(&self) -> &'static str;
+
+    /// The starting position in FEN.
+    fn start_fen(&self) -> &'static str {
+        STANDARD_FEN
+    }
+
+    /// Whether `colour` has a king that may not be left in check. Positions
+    /// must then have exactly one king of that colour.
+    fn royal_king(&self, _colour: Colour) -> bool {
+        true
+    }
+
+    /// Whether pawns of `colour` may stand on their own first rank, from
+    /// which they can move two squares.
+    fn first_rank_pawns(&self, _colour: Colour) -> bool {
+        false
+    }
+
+    /// The pieces a pawn may promote to.
+    fn promotions(&self) -> &'static [Variant] {
+        &[Knight, Bishop, Rook, Queen]
+    }
+
+    /// Whether captured pieces go into the capturer's pocket, from which they
+    /// can be dropped back onto the board as a move.
+    fn drops(&self) -> bool {
+        false
+    }
+
+    /// Whether a player who can capture must do so.
+    fn forced_captures(&self) -> bool {
+        false
+    }
+
+    /// Whether captures explode, removing the capturing piece along with
+    /// every piece other than a pawn next to the capture. Kings may then not
+    /// capture.
+    fn explosions(&self) -> bool {
+        false
+    }
+
+    /// Whether a move may put the opponent in check.
+    fn allows_check(&self) -> bool {
+        true
+    }
+
+    /// The number of checks that wins the game, if there is one. Positions
+    /// then count the checks each side has given.
+    fn check_limit(&self) -> Option<u32> {
+        None
+    }
+
+    /// Ends the game before the side to move plays, for variants with goals
+    /// of their own. The position's legal moves are not known yet.
+    fn outcome(&self, _state: &State) -> Option<EndState> {
+        None
+    }
+
+    /// How the game ends when the side to move has no legal moves.
+    fn no_moves(&self, state: &State) -> EndState {
+        if state.in_check() {
+            EndState::Checkmate(state.turn.flipped())
+        } else {
+            EndState::Stalemate
+        }
+    }
+
+    /// Whether neither side can win any more, drawing the game.
+    fn insufficient_material(&self, state: &State) -> bool {
+        !state.check_material()
+    }
+}
+
+/// The rules with the given name, ignoring case. Chess960 is played by the
+/// standard rules, its castling coming from the starting position.
+pub fn find_rules(name: &str) -> Option<&'static dyn Rules> {
+    if name.eq_ignore_ascii_case("Chess960") || name.eq_ignore_ascii_case("From Position") {
+        return Some(&Standard);
+    }
+    RULES
+        .iter()
+        .copied()
+        .find(|rules| rules.name().eq_ignore_ascii_case(name))
+}
+
+/// Standard chess.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Standard;
+
+impl Rules for Standard {
+    fn name(&self) -> &'static str {
+        "Standard"
+    }
+}
+
+/// King of the Hill: a player also wins by bringing their king to one of the
+/// four centre squares.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KingOfTheHill;
+
+impl Rules for KingOfTheHill {
+    fn name(&self) -> &'static str {
+        "King of the Hill"
+    }
+
+    fn outcome(&self, state: &State) -> Option<EndState> {
+        let mover = state.turn.flipped();
+        match state.board.king(mover) {
+            Some(king) if bit(king) & CENTRE != 0 => Some(EndState::VariantWin(mover)),
+            _ => None,
+        }
+    }
+
+    // A bare king can still walk to the centre.
+    fn insufficient_material(&self, _state: &State) -> bool {
+        false
+    }
+}
+
+/// Three-check: a player also wins by giving check for the third time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreeCheck;
+
+impl Rules for ThreeCheck {
+    fn name(&self) -> &'static str {
+        "Three-check"
+    }
+
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 +0+0"
+    }
+
+    fn check_limit(&self) -> Option<u32> {
+        Some(3)
+    }
+
+    fn outcome(&self, state: &State) -> Option<EndState> {
+        let mover = state.turn.flipped();
+        if state.checks_given(mover) >= 3 {
+            return Some(EndState::VariantWin(mover));
+        }
+        None
+    }
+
+    // Any piece can give check, so only bare kings are a draw.
+    fn insufficient_material(&self, state: &State) -> bool {
+        bare_kings(state)
+    }
+}
+
+/// Atomic chess: captures explode, and a player wins by blowing up the
+/// opponent's king.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Atomic;
+
+impl Rules for Atomic {
+    fn name(&self) -> &'static str {
+        "Atomic"
+    }
+
+    fn explosions(&self) -> bool {
+        true
+    }
+
+    fn outcome(&self, state: &State) -> Option<EndState> {
+        if state.board.king(state.turn).is_none() {
+            return Some(EndState::VariantWin(state.turn.flipped()));
+        }
+        None
+    }
+
+    fn insufficient_material(&self, state: &State) -> bool {
+        bare_kings(state)
+    }
+}
+
+/// Antichess: captures are compulsory, the king is an ordinary piece, and a
+/// player wins by losing all their pieces or being stalemated.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Antichess;
+
+impl Rules for Antichess {
+    fn name(&self) -> &'static str {
+        "Antichess"
+    }
+
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"
+    }
+
+    fn royal_king(&self, _colour: Colour) -> bool {
+        false
+    }
+
+    fn promotions(&self) -> &'static [Variant] {
+        &[Knight, Bishop, Rook, Queen, King]
+    }
+
+    fn forced_captures(&self) -> bool {
+        true
+    }
+
+    fn no_moves(&self, state: &State) -> EndState {
+        EndState::VariantWin(state.turn)
+    }
+
+    fn insufficient_material(&self, _state: &State) -> bool {
+        false
+    }
+}
+
+/// Horde: White has 36 pawns and no king, and wins by checkmate, while Black
+/// wins by capturing every white piece.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Horde;
+
+impl Rules for Horde {
+    fn name(&self) -> &'static str {
+        "Horde"
+    }
+
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1"
+    }
+
+    fn royal_king(&self, colour: Colour) -> bool {
+        colour == Black
+    }
+
+    fn first_rank_pawns(&self, colour: Colour) -> bool {
+        colour == White
+    }
+
+    fn no_moves(&self, state: &State) -> EndState {
+        if state.board.colour(state.turn) == 0 {
+            EndState::VariantWin(state.turn.flipped())
+        } else if state.in_check() {
+            EndState::Checkmate(state.turn.flipped())
+        } else {
+            EndState::Stalemate
+        }
+    }
+
+    fn insufficient_material(&self, _state: &State) -> bool {
+        false
+    }
+}
+
+/// Racing Kings: no one may give check, and the first king to reach the
+/// eighth rank wins. If Black's king can reach it on the move after White's
+/// does, the game is drawn.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RacingKings;
+
+impl Rules for RacingKings {
+    fn name(&self) -> &'static str {
+        "Racing Kings"
+    }
+
+    fn start_fen(&self) -> &'static str {
+        "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1"
+    }
+
+    fn allows_check(&self) -> bool {
+        false
+    }
+
+    fn outcome(&self, state: &State) -> Option<EndState> {
+        let arrived = |colour| state.board.pieces(colour, King) & EIGHTH_RANK != 0;
+        match (arrived(White), arrived(Black)) {
+            (true, true) => Some(EndState::VariantDraw),
+            (false, true) => Some(EndState::VariantWin(Black)),
+            (true, false) if state.turn == Black && can_arrive(state) => None,
+            (true, false) => Some(EndState::VariantWin(White)),
+            (false, false) => None,
+        }
+    }
+
+    fn insufficient_material(&self, _state: &State) -> bool {
+        false
+    }
+}
+
+/// Crazyhouse: captured pieces change sides and go into the capturer's
+/// pocket, and a move may drop one back onto any empty square.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Crazyhouse;
+
+impl Rules for Crazyhouse {
+    fn name(&self) -> &'static str {
+        "Crazyhouse"
+    }
+
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1"
+    }
+
+    fn drops(&self) -> bool {
+        true
+    }
+
+    // Captured pieces come back, so there is always mating material.
+    fn insufficient_material(&self, _state: &State) -> bool {
+        false
+    }
+}
+
+// Whether Black's king has a legal move to the eighth rank.
+fn can_arrive(state: &State) -> bool {
+    let king = match state.board.king(Black) {
+        Some(king) => king,
+        None => return false,
+    };
+    squares(king_attacks(king) & EIGHTH_RANK & !state.board.colour(Black)).any(|to| {
+        let mut board = state.board;
+        board.remove(to);
+        board.move_piece(king, to);
+        !board.in_check(Black) && !board.in_check(White)
+    })
+}
+
+fn bare_kings(state: &State) -> bool {
+    state.board.occupied() == state.board.variant(King)
+}
@@ -0,0 +1,193 @@
+use crate::state::MoveType::*;
+use crate::*;
+
+impl State {
+    /// Writes a move in Standard Algebraic Notation, e.g. `Nbd7`, `exd6`,
+    /// `e8=Q#` or the drop `N@f3`. Returns `None` if the move is not legal
+    /// here.
+    pub fn to_san(&self, mv: Move) -> Option<String> {
+        if !self.is_legal(mv) {
+            return None;
+        }
+
+        let mut san = String::new();
+        if let MoveKind::Drop(variant) = mv.kind {
+            san.push(variant.to_char());
+            san.push('@');
+            san.push_str(&mv.to.to_string());
+        } else if mv.kind == MoveKind::Castle {
+            san.push_str(if mv.to.get_x() > mv.from.get_x() {
+                "O-O"
+            } else {
+                "O-O-O"
+            });
+        } else {
+            let moving = self.board.get(mv.from).unwrap();
+            if moving.variant == Pawn {
+                if mv.captured.is_some() {
+                    san.push(mv.from.to_string().chars().next().unwrap());
+                }
+            } else {
+                san.push(moving.variant.to_char());
+                // Kings need telling apart too where they are not royal, as
+                // Antichess allows more than one.
+                let others: Vec<Position> = self
+                    .moves
+                    .iter()
+                    .filter(|&&(f, t, _)| {
+                        t == mv.to && f != mv.from && self.board.get(f) == Some(moving)
+                    })
+                    .map(|&(f, _, _)| f)
+                    .collect();
+                if !others.is_empty() {
+                    let name = mv.from.to_string();
+                    if others.iter().all(|f| f.get_x() != mv.from.get_x()) {
+                        san.push_str(&name[..1]);
+                    } else if others.iter().all(|f| f.get_y() != mv.from.get_y()) {
+                        san.push_str(&name[1..]);
+                    } else {
+                        san.push_str(&name);
+                    }
+                }
+            }
+            if mv.captured.is_some() {
+                san.push('x');
+            }
+            san.push_str(&mv.to.to_string());
+            if let Some(variant) = mv.promotion {
+                san.push('=');
+                san.push(variant.to_char());
+            }
+        }
+
+        let mut state = self.clone();
+        state.apply(mv);
+        if let Some(EndState::Checkmate(_)) = state.ended {
+            san.push('#');
+        } else if state.in_check() {
+            san.push('+');
+        }
+        Some(san)
+    }
+
+    /// Reads a move in Standard Algebraic Notation. Check and annotation
+    /// suffixes are ignored, and castling may be written with `O` or `0`.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        if self.info.promoting.is_some() {
+            return Err(SanError::Illegal);
+        }
+        let san = san.trim_end_matches(&['+', '#', '!', '?'][..]);
+
+        if san == "O-O" || san == "O-O-O" || san == "0-0" || san == "0-0-0" {
+            let short = san.len() == 3;
+            return self
+                .moves
+                .iter()
+                .find(|&&(from, to, info)| {
+                    matches!(info.move_type, Castle { .. }) && (to.get_x() > from.get_x()) == short
+                })
+                .map(|&(from, to, info)| self.to_move(from, to, &info, None))
+                .ok_or(SanError::Illegal);
+        }
+
+        // Drops are written with the piece letter, which pawns may leave out.
+        if let Some((piece, to)) = san.split_once('@') {
+            let variant = match piece {
+                "" => Pawn,
+                _ if piece.len() == 1 => {
+                    Variant::from_char(piece.chars().next().unwrap()).ok_or(SanError::Invalid)?
+                }
+                _ => return Err(SanError::Invalid),
+            };
+            let to = Position::parse(to).ok_or(SanError::Invalid)?;
+            return self.find_drop(variant, to).ok_or(SanError::Illegal);
+        }
+
+        let mut chars: Vec<char> = san.chars().collect();
+        let variant = match chars.first().and_then(|&c| Variant::from_char(c)) {
+            Some(variant) if variant != Pawn => {
+                chars.remove(0);
+                variant
+            }
+            _ => Pawn,
+        };
+
+        let promotion = match chars.last().and_then(|&c| Variant::from_char(c)) {
+            Some(promotion) if variant == Pawn => {
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+                Some(promotion)
+            }
+            _ => None,
+        };
+
+        if chars.len() < 2 {
+            return Err(SanError::Invalid);
+        }
+        let to: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to = Position::parse(&to).ok_or(SanError::Invalid)?;
+        if chars.last() == Some(&'x') {
+            chars.pop();
+        }
+
+        let mut file = None;
+        let mut rank = None;
+        for c in chars {
+            match c {
+                'a'..='h' if file.is_none() && rank.is_none() => file = Some(c as i8 - 'a' as i8),
+                '1'..='8' if rank.is_none() => rank = Some(c as i8 - '1' as i8),
+                _ => return Err(SanError::Invalid),
+            }
+        }
+
+        let mut found = None;
+        for &(from, t, info) in self.moves.iter() {
+            if t == to
+                && !matches!(info.move_type, Drop(_))
+                && self.board.get(from).unwrap().variant == variant
+                && (file.is_none() || file == Some(from.get_x()))
+                && (rank.is_none() || rank == Some(from.get_y()))
+                && info.state_info.promoting.is_some() == promotion.is_some()
+                && !matches!(info.move_type, Castle { .. })
+            {
+                if found.is_some() {
+                    return Err(SanError::Ambiguous);
+                }
+                found = Some(from);
+            }
+        }
+
+        found
+            .and_then(|from| self.find_move(from, to, promotion))
+            .ok_or(SanError::Illegal)
+    }
+}
+
+/// An error from [`State::parse_san`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanError {
+    /// The text is not a well-formed SAN move.
+    Invalid,
+    /// No legal move matches.
+    Illegal,
+    /// More than one legal move matches.
+    Ambiguous,
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                SanError::Invalid => "not valid algebraic notation",
+                SanError::Illegal => "no legal move matches",
+                SanError::Ambiguous => "more than one legal move matches",
+            }
+        )
+    }
+}
+
+impl std::error::Error for SanError {}
@@ -0,0 +1,576 @@
+use crate::state::{promotions, MoveInfo, MoveType};
+use crate::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const MAX_PLY: usize = 64;
+const INFINITY: i32 = 32000;
+const MATE: i32 = 31000;
+// Scores beyond this are mates, counted in plies from the mating position.
+const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+
+const TT_MOVE: i32 = 1 << 30;
+const CAPTURE: i32 = 1 << 28;
+const KILLER: i32 = 1 << 27;
+const MAX_HISTORY: i32 = 1 << 26;
+
+/// How far a search may go. The search stops at whichever limit it reaches
+/// first, and the first iteration is always completed so that there is a move
+/// to play. With no limits it runs to the maximum depth unless stopped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// The greatest depth to search to, in plies.
+    pub depth: Option<u32>,
+    /// The number of positions to visit before stopping.
+    pub nodes: Option<u64>,
+    /// How long the search may run.
+    pub time: Option<Duration>,
+}
+
+/// The value of a position from the point of view of the side to move.
+///
+/// Displays as a signed number of pawns such as `+0.35`, or as a mate such as
+/// `#3` or `#-2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    /// An advantage in hundredths of a pawn.
+    Centipawns(i32),
+    /// A forced mate in this many moves, negative when the side to move is the
+    /// one being mated.
+    Mate(i32),
+}
+
+impl Score {
+    fn from_internal(score: i32) -> Score {
+        if score >= MATE_BOUND {
+            Score::Mate((MATE - score + 1) / 2)
+        } else if score <= -MATE_BOUND {
+            Score::Mate(-(MATE + score + 1) / 2)
+        } else {
+            Score::Centipawns(score)
+        }
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Score::Centipawns(centipawns) => write!(f, "{:+.2}", centipawns as f64 / 100.0),
+            Score::Mate(moves) => write!(f, "#{}", moves),
+        }
+    }
+}
+
+/// The outcome of a search, or of one iteration of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    /// The move to play, or `None` if the game is over.
+    pub best_move: Option<Move>,
+    /// The value of the position after the best move.
+    pub score: Score,
+    /// The principal variation: the line both sides are expected to play,
+    /// starting with the best move.
+    pub pv: Vec<Move>,
+    /// The depth of the last completed iteration.
+    pub depth: u32,
+    /// The number of positions visited.
+    pub nodes: u64,
+    /// How long the search took.
+    pub time: Duration,
+}
+
+// A move as the search sees it, before it is turned into a `Move`.
+#[derive(Clone, Copy)]
+struct SearchMove {
+    from: Position,
+    to: Position,
+    info: MoveInfo,
+    promotion: Option<Variant>,
+    captured: Option<Variant>,
+    order: i32,
+}
+
+impl SearchMove {
+    // Drops are told apart by the piece dropped.
+    fn key(&self) -> MoveKey {
+        match self.info.move_type {
+            MoveType::Drop(piece) => (self.from, self.to, Some(piece.variant)),
+            _ => (self.from, self.to, self.promotion),
+        }
+    }
+
+    fn is_quiet(&self) -> bool {
+        self.captured.is_none() && self.promotion.is_none()
+    }
+}
+
+type MoveKey = (Position, Position, Option<Variant>);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    hash: u64,
+    depth: i32,
+    score: i32,
+    bound: Bound,
+    mv: Option<MoveKey>,
+}
+
+/// An iterative-deepening alpha-beta search.
+///
+/// Each iteration searches one ply deeper than the last, with a quiescence
+/// search of captures at the leaves. Moves are tried in the order of the
+/// transposition table's best move, captures by most valuable victim and least
+/// valuable attacker, killer moves and then the history heuristic. The
+/// transposition table, killers and history are kept between searches, so
+/// reusing a `Searcher` through a game makes it faster.
+///
+/// ```
+/// use chess_core::{Limits, Searcher, State};
+///
+/// let state = State::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+/// let result = Searcher::new().search(
+///     &state,
+///     Limits {
+///         depth: Some(3),
+///         ..Default::default()
+///     },
+/// );
+/// assert_eq!(result.best_move.unwrap().to_string(), "a1a8");
+/// ```
+pub struct Searcher {
+    table: Vec<Option<Entry>>,
+    killers: [[Option<MoveKey>; 2]; MAX_PLY],
+    history: Box<[[[i32; 64]; 64]; 2]>,
+    positions: Vec<u64>,
+    limits: Limits,
+    depth: u32,
+    nodes: u64,
+    start: Instant,
+    stop: Arc<AtomicBool>,
+    stopped: bool,
+    params: Params,
+}
+
+impl Default for Searcher {
+    fn default() -> Searcher {
+        Searcher::new()
+    }
+}
+
+impl Searcher {
+    /// A searcher with a 16 MB transposition table.
+    pub fn new() -> Searcher {
+        Searcher::with_hash_size(16)
+    }
+
+    /// A searcher with a transposition table of about `megabytes` in size.
+    pub fn with_hash_size(megabytes: usize) -> Searcher {
+        let entries = megabytes * 1024 * 1024 / std::mem::size_of::<Option<Entry>>();
+        let entries = (entries / 2 + 1).next_power_of_two();
+        Searcher {
+            table: vec![None; entries],
+            killers: [[None; 2]; MAX_PLY],
+            history: Box::new([[[0; 64]; 64]; 2]),
+            positions: Vec::new(),
+            limits: Limits::default(),
+            depth: 0,
+            nodes: 0,
+            start: Instant::now(),
+            stop: Arc::new(AtomicBool::new(false)),
+            stopped: false,
+            params: Params::default(),
+        }
+    }
+
+    /// A flag that stops a running search from another thread when set. The
+    /// search returns the result of its last completed iteration, and the flag
+    /// is cleared when the next search starts.
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// Forgets everything learned from earlier searches, for a new game.
+    pub fn clear(&mut self) {
+        self.table.iter_mut().for_each(|entry| *entry = None);
+        self.killers = [[None; 2]; MAX_PLY];
+        *self.history = [[[0; 64]; 64]; 2];
+        self.positions.clear();
+    }
+
+    /// Sets the weights of the evaluation. Results stored from searches with
+    /// the old weights are forgotten.
+    pub fn set_params(&mut self, params: Params) {
+        self.params = params;
+        self.table.iter_mut().for_each(|entry| *entry = None);
+    }
+
+    /// Sets the hashes of the positions earlier in the game, oldest first, so
+    /// that the search treats repeating one of them as a draw.
+    pub fn set_history(&mut self, positions: &[u64]) {
+        self.positions = positions.to_vec();
+    }
+
+    /// Searches `state` for the best move within `limits`.
+    pub fn search(&mut self, state: &State, limits: Limits) -> SearchResult {
+        self.search_with(state, limits, |_| ())
+    }
+
+    /// Searches like [`Searcher::search`], calling `report` with the result of
+    /// each completed iteration.
+    pub fn search_with(
+        &mut self,
+        state: &State,
+        limits: Limits,
+        mut report: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        self.limits = limits;
+        self.nodes = 0;
+        self.start = Instant::now();
+        self.stop.store(false, Ordering::Relaxed);
+        self.stopped = false;
+        self.killers = [[None; 2]; MAX_PLY];
+        for from in self.history.iter_mut().flat_map(|colour| colour.iter_mut()) {
+            from.iter_mut().for_each(|score| *score /= 8);
+        }
+
+        let mut result = SearchResult {
+            best_move: None,
+            score: Score::Centipawns(0),
+            pv: Vec::new(),
+            depth: 0,
+            nodes: 0,
+            time: Duration::from_secs(0),
+        };
+        if state.ended().is_some() || state.promoting() {
+            return result;
+        }
+
+        let mut state = state.clone();
+        let max_depth = limits
+            .depth
+            .unwrap_or(MAX_PLY as u32)
+            .clamp(1, MAX_PLY as u32 / 2);
+        for depth in 1..=max_depth {
+            self.depth = depth;
+            let mut pv = Vec::new();
+            let score = self.negamax(&mut state, depth as i32, 0, -INFINITY, INFINITY, &mut pv);
+            if self.stopped {
+                break;
+            }
+            result = SearchResult {
+                best_move: pv.first().copied(),
+                score: Score::from_internal(score),
+                pv,
+                depth,
+                nodes: self.nodes,
+                time: self.start.elapsed(),
+            };
+            report(&result);
+
+            // A mate found within the search depth cannot be improved on, and
+            // the next iteration would take longer than all the ones before.
+            if score.abs() >= MATE - depth as i32
+                || matches!(limits.nodes, Some(nodes) if self.nodes >= nodes)
+                || matches!(limits.time, Some(time) if self.start.elapsed() * 2 > time)
+            {
+                break;
+            }
+        }
+        result.nodes = self.nodes;
+        result.time = self.start.elapsed();
+        result
+    }
+
+    fn negamax(
+        &mut self,
+        state: &mut State,
+        mut depth: i32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        pv.clear();
+        if ply > 0 && self.is_draw(state) {
+            return 0;
+        }
+        if let Some(end) = state.rules.outcome(state).filter(|_| ply > 0) {
+            return end_score(&end, state.turn, ply);
+        }
+        let in_check = state.in_check();
+        if in_check {
+            depth += 1;
+        }
+        if depth <= 0 || ply >= MAX_PLY - 1 {
+            return self.quiesce(state, ply, alpha, beta);
+        }
+        self.visit();
+        if self.stopped {
+            return 0;
+        }
+
+        let hash = state.hash();
+        let entry = self.probe(hash);
+        if let Some(entry) = entry {
+            if ply > 0 && entry.depth >= depth {
+                let score = from_table(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => (),
+                }
+            }
+        }
+
+        let moves = self.ordered_moves(state, ply, entry.and_then(|entry| entry.mv), false);
+        if moves.is_empty() {
+            return end_score(&state.rules.no_moves(state), state.turn, ply);
+        }
+
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        let mut line = Vec::new();
+        self.positions.push(hash);
+        for mv in moves {
+            let public = state.to_move(mv.from, mv.to, &mv.info, mv.promotion);
+            let undo = state.make(mv.from, mv.to, mv.info, mv.promotion);
+            let score = -self.negamax(state, depth - 1, ply + 1, -beta, -alpha, &mut line);
+            state.unmake(undo);
+            if self.stopped {
+                self.positions.pop();
+                return 0;
+            }
+
+            if score > best {
+                best = score;
+                best_move = Some(mv.key());
+                if score > alpha {
+                    alpha = score;
+                    pv.clear();
+                    pv.push(public);
+                    pv.extend_from_slice(&line);
+                }
+            }
+            if alpha >= beta {
+                if mv.is_quiet() {
+                    self.record_cutoff(state.turn, mv, ply, depth);
+                }
+                break;
+            }
+        }
+        self.positions.pop();
+
+        let bound = if best >= beta {
+            Bound::Lower
+        } else if best > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.store(Entry {
+            hash,
+            depth,
+            score: to_table(best, ply),
+            bound,
+            mv: best_move,
+        });
+        best
+    }
+
+    // Searches captures until the position is quiet, so that the evaluation
+    // is not taken in the middle of an exchange. In check every move is
+    // searched, which also finds checkmates.
+    fn quiesce(&mut self, state: &mut State, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.visit();
+        if self.stopped {
+            return 0;
+        }
+        if let Some(end) = state.rules.outcome(state) {
+            return end_score(&end, state.turn, ply);
+        }
+        let in_check = state.in_check();
+        let mut best = -INFINITY;
+        if !in_check || ply >= MAX_PLY - 1 {
+            best = self.params.evaluate(state);
+            if best >= beta || ply >= MAX_PLY - 1 {
+                return best;
+            }
+            alpha = alpha.max(best);
+        }
+
+        let moves = self.ordered_moves(state, ply, None, !in_check);
+        if in_check && moves.is_empty() {
+            return end_score(&state.rules.no_moves(state), state.turn, ply);
+        }
+        for mv in moves {
+            let undo = state.make(mv.from, mv.to, mv.info, mv.promotion);
+            let score = -self.quiesce(state, ply + 1, -beta, -alpha);
+            state.unmake(undo);
+            if self.stopped {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                alpha = alpha.max(score);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        best
+    }
+
+    // The legal moves, best first. With `captures_only` quiet moves and
+    // underpromotions are left out.
+    fn ordered_moves(
+        &self,
+        state: &State,
+        ply: usize,
+        tt_move: Option<MoveKey>,
+        captures_only: bool,
+    ) -> Vec<SearchMove> {
+        let mut infos = Vec::new();
+        state.gen_legal_into(&mut infos);
+
+        let mut moves = Vec::with_capacity(infos.len());
+        for (from, to, info) in infos {
+            let attacker = match info.move_type {
+                MoveType::Drop(piece) => piece.variant,
+                _ => state.board.get(from).unwrap().variant,
+            };
+            let captured = state
+                .to_move(from, to, &info, None)
+                .captured
+                .map(|piece| piece.variant);
+            for promotion in promotions(state.rules, info) {
+                let underpromotion = matches!(promotion, Some(variant) if variant != Queen);
+                if captures_only && (underpromotion || captured.is_none() && promotion.is_none()) {
+                    continue;
+                }
+                let mut mv = SearchMove {
+                    from,
+                    to,
+                    info,
+                    promotion,
+                    captured,
+                    order: 0,
+                };
+                mv.order = if Some(mv.key()) == tt_move {
+                    TT_MOVE
+                } else if underpromotion {
+                    -1
+                } else if captured.is_some() || promotion.is_some() {
+                    CAPTURE
+                        + captured.map_or(0, |victim| eval::value(victim) * 16)
+                        + promotion.map_or(0, eval::value)
+                        - eval::value(attacker)
+                } else if Some(mv.key()) == self.killers[ply][0] {
+                    KILLER + 1
+                } else if Some(mv.key()) == self.killers[ply][1] {
+                    KILLER
+                } else {
+                    self.history[state.turn as usize][from.value as usize][to.value as usize]
+                };
+                moves.push(mv);
+            }
+        }
+        moves.sort_by_key(|mv| std::cmp::Reverse(mv.order));
+        moves
+    }
+
+    fn record_cutoff(&mut self, colour: Colour, mv: SearchMove, ply: usize, depth: i32) {
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(mv.key()) {
+            killers[1] = killers[0];
+            killers[0] = Some(mv.key());
+        }
+
+        let history = &mut self.history[colour as usize];
+        history[mv.from.value as usize][mv.to.value as usize] += depth * depth;
+        if history[mv.from.value as usize][mv.to.value as usize] >= MAX_HISTORY {
+            for from in history.iter_mut() {
+                from.iter_mut().for_each(|score| *score /= 2);
+            }
+        }
+    }
+
+    // Whether the position is drawn by the fifty-move rule, insufficient
+    // material or by repeating a position from earlier in the game or search.
+    fn is_draw(&self, state: &State) -> bool {
+        state.halfmove_clock() >= 100
+            || state.rules.insufficient_material(state)
+            || self
+                .positions
+                .iter()
+                .rev()
+                .take(state.halfmove_clock() as usize)
+                .any(|&hash| hash == state.hash())
+    }
+
+    fn visit(&mut self) {
+        self.nodes += 1;
+        if self.depth == 1 {
+            return;
+        }
+        if matches!(self.limits.nodes, Some(nodes) if self.nodes >= nodes) {
+            self.stopped = true;
+        }
+        if self.nodes & 1023 == 0
+            && (self.stop.load(Ordering::Relaxed)
+                || matches!(self.limits.time, Some(time) if self.start.elapsed() >= time))
+        {
+            self.stopped = true;
+        }
+    }
+
+    fn probe(&self, hash: u64) -> Option<Entry> {
+        self.table[hash as usize & (self.table.len() - 1)].filter(|entry| entry.hash == hash)
+    }
+
+    fn store(&mut self, entry: Entry) {
+        let index = entry.hash as usize & (self.table.len() - 1);
+        self.table[index] = Some(entry);
+    }
+}
+
+// Mate scores are stored relative to the position they are found in, so that
+// they stay correct when the position is reached at a different ply.
+fn to_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score + ply as i32
+    } else if score <= -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn from_table(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score - ply as i32
+    } else if score <= -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+// The score of a finished game for the side to move, with wins and losses
+// scored like checkmate so that quicker wins are preferred.
+fn end_score(end: &EndState, turn: Colour, ply: usize) -> i32 {
+    match end.winner() {
+        Some(winner) if winner == turn => MATE - ply as i32,
+        Some(_) => -MATE + ply as i32,
+        None => 0,
+    }
+}
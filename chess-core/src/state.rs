@@ -0,0 +1,1133 @@
+use crate::bitboard::*;
+use crate::*;
+use std::collections::{HashMap, HashSet};
+use MoveType::*;
+
+const BACK_RANKS: Bitboard = 0xff00_0000_0000_00ff;
+
+/// A complete chess position, together with its legal moves and whether the
+/// game has ended.
+#[derive(Clone)]
+pub struct State {
+    pub(crate) board: Board,
+    pub(crate) moves: Vec<(Position, Position, MoveInfo)>,
+    pub(crate) turn: Colour,
+    pub(crate) info: StateInfo,
+    pub(crate) rooks: [Position; 4],
+    pub(crate) chess960: bool,
+    pub(crate) rules: &'static dyn Rules,
+    // Checks given by each side, counted when the rules have a check limit.
+    pub(crate) checks: [u32; 2],
+    // Pieces in hand by colour and variant, and the squares of pieces that
+    // were promoted, which go back into a pocket as pawns. Only used when the
+    // rules allow drops.
+    pub(crate) pockets: [[u32; 5]; 2],
+    pub(crate) promoted: Bitboard,
+    pub(crate) halfmove_clock: u32,
+    pub(crate) fullmove_number: u32,
+    pub(crate) hash: u64,
+    pub(crate) ended: Option<EndState>,
+}
+
+impl Default for State {
+    fn default() -> State {
+        State::new()
+    }
+}
+
+impl State {
+    /// The standard starting position.
+    pub fn new() -> State {
+        State::with_rules(&Standard)
+    }
+
+    /// The starting position of a variant.
+    pub fn with_rules(rules: &'static dyn Rules) -> State {
+        State::from_fen_with_rules(rules.start_fen(), rules).unwrap()
+    }
+
+    /// The rules the game is played by.
+    pub fn rules(&self) -> &'static dyn Rules {
+        self.rules
+    }
+
+    /// How many pieces of the given variant `colour` has in hand, in variants
+    /// with drops.
+    pub fn pocket(&self, colour: Colour, variant: Variant) -> u32 {
+        match variant {
+            King => 0,
+            _ => self.pockets[colour as usize][variant as usize],
+        }
+    }
+
+    /// How many times `colour` has given check, in variants with a check
+    /// limit.
+    pub fn checks_given(&self, colour: Colour) -> u32 {
+        self.checks[colour as usize]
+    }
+
+    pub(crate) fn gen_legal_moves(&mut self) {
+        if let Some(end) = self.rules.outcome(self) {
+            self.ended = Some(end);
+            self.moves = Vec::new();
+            return;
+        }
+        self.moves = self.legal_move_infos();
+
+        if self.moves.is_empty() {
+            self.ended = Some(self.rules.no_moves(self));
+        } else if self.rules.insufficient_material(self) {
+            self.ended = Some(EndState::InsufficientMaterial);
+            self.moves = Vec::new();
+        } else if self.halfmove_clock >= 150 {
+            self.ended = Some(EndState::SeventyFiveMoveRule);
+            self.moves = Vec::new();
+        }
+    }
+
+    fn legal_move_infos(&self) -> Vec<(Position, Position, MoveInfo)> {
+        let mut moves = Vec::new();
+        self.gen_legal_into(&mut moves);
+        moves
+    }
+
+    pub(crate) fn gen_legal_into(&self, moves: &mut Vec<(Position, Position, MoveInfo)>) {
+        let start = moves.len();
+        self.gen_moves(moves);
+        if self.rules.royal_king(self.turn) {
+            self.remove_illegal(moves, start);
+        }
+
+        if self.rules.forced_captures() {
+            let capture = |&(_, to, info): &(Position, Position, MoveInfo)| match info.move_type {
+                Normal => self.board.get(to).is_some(),
+                EnPassant(_) => true,
+                Castle { .. } | Drop(_) => false,
+            };
+            if moves[start..].iter().any(capture) {
+                let mut kept = start;
+                for i in start..moves.len() {
+                    if capture(&moves[i]) {
+                        moves[kept] = moves[i];
+                        kept += 1;
+                    }
+                }
+                moves.truncate(kept);
+            }
+        }
+    }
+
+    // Removes the moves that leave the king in check. Variants where captures
+    // explode or where check may not be given try every move out in full.
+    fn remove_illegal(&self, moves: &mut Vec<(Position, Position, MoveInfo)>, start: usize) {
+        let mut board = self.board;
+        let colour = self.turn;
+        let simple = self.rules.allows_check() && !self.rules.explosions();
+        let king = board.king(colour);
+        let pinned = king.map_or(0, |king| board.pinned(king, colour));
+        let check = self.in_check();
+        let mut kept = start;
+        for i in start..moves.len() {
+            let (from, to, info) = moves[i];
+            let legal = if !simple {
+                let mut board = self.board;
+                make_on(self.rules, &mut board, from, to, info.move_type);
+                self.allowed(&board)
+            } else if !check
+                && Some(from) != king
+                && pinned & bit(from) == 0
+                && !matches!(info.move_type, EnPassant(_))
+            {
+                true
+            } else {
+                let captured = info.move_type.make(&mut board, from, to);
+                let legal = !board.in_check(colour);
+                info.move_type.unmake(&mut board, from, to, captured);
+                legal
+            };
+            if legal {
+                moves[kept] = moves[i];
+                kept += 1;
+            }
+        }
+        moves.truncate(kept);
+    }
+
+    // Whether the side to move may leave the board like this after its move.
+    // Blowing up the enemy king wins at once, even if the own king is left in
+    // check.
+    fn allowed(&self, board: &Board) -> bool {
+        let (colour, enemy) = (self.turn, self.turn.flipped());
+        if self.rules.royal_king(enemy) && board.king(enemy).is_none() {
+            return board.king(colour).is_some();
+        }
+        !self.exposed(board, colour) && (self.rules.allows_check() || !self.exposed(board, enemy))
+    }
+
+    // Whether the king of `colour` is in check, or missing where it is royal.
+    pub(crate) fn exposed(&self, board: &Board, colour: Colour) -> bool {
+        if !self.rules.royal_king(colour) {
+            return false;
+        }
+        match board.king(colour) {
+            // A king next to the enemy king cannot be taken when captures
+            // explode, as the capture would blow up the capturing king too.
+            Some(king) => {
+                board.is_attacked(king, colour.flipped())
+                    && !(self.rules.explosions()
+                        && king_attacks(king) & board.pieces(colour.flipped(), King) != 0)
+            }
+            None => true,
+        }
+    }
+
+    fn gen_moves(&self, moves: &mut Vec<(Position, Position, MoveInfo)>) {
+        let own = self.board.colour(self.turn);
+        for from in squares(own) {
+            let piece = self.board.get(from).unwrap();
+            match piece.variant {
+                Pawn => self.gen_pawn_moves(moves, from),
+                _ => {
+                    let mut targets = self.board.attacks(from, piece) & !own;
+                    if piece.variant == King && self.rules.explosions() {
+                        targets &= !self.board.occupied();
+                    }
+                    for to in squares(targets) {
+                        moves.push((from, to, self.move_info(from, to, Normal)));
+                    }
+                    if piece.variant == King && self.rules.royal_king(self.turn) {
+                        self.gen_castling_moves(moves, from);
+                    }
+                }
+            }
+        }
+        if self.rules.drops() {
+            self.gen_drops(moves);
+        }
+    }
+
+    // A piece in hand may be dropped on any empty square, except for pawns on
+    // the first and last ranks. Drops are stored as moves from the square
+    // they land on.
+    fn gen_drops(&self, moves: &mut Vec<(Position, Position, MoveInfo)>) {
+        let empty = !self.board.occupied();
+        for &variant in [Pawn, Knight, Bishop, Rook, Queen].iter() {
+            if self.pocket(self.turn, variant) == 0 {
+                continue;
+            }
+            let targets = match variant {
+                Pawn => empty & !BACK_RANKS,
+                _ => empty,
+            };
+            let piece = Piece {
+                colour: self.turn,
+                variant,
+            };
+            for to in squares(targets) {
+                let info = MoveInfo {
+                    move_type: Drop(piece),
+                    ..Default::default()
+                };
+                moves.push((to, to, info));
+            }
+        }
+    }
+
+    fn gen_pawn_moves(&self, moves: &mut Vec<(Position, Position, MoveInfo)>, from: Position) {
+        let (y, start, first, last) = if self.turn == White {
+            (1, 1, 0, 7)
+        } else {
+            (-1, 6, 7, 0)
+        };
+        let occupied = self.board.occupied();
+
+        let mut targets = pawn_attacks(from, self.turn) & self.board.colour(self.turn.flipped());
+        if let Some(to) = from.offset_by(0, y) {
+            if occupied & bit(to) == 0 {
+                targets |= bit(to);
+                let first_rank = from.get_y() == first && self.rules.first_rank_pawns(self.turn);
+                if from.get_y() == start || first_rank {
+                    let double = from.offset_by(0, y * 2).unwrap();
+                    if occupied & bit(double) == 0 {
+                        let mut info = self.move_info(from, double, Normal);
+                        // Only a pawn from the second rank can be taken en
+                        // passant.
+                        if !first_rank {
+                            info.state_info.en_passant = Some(to);
+                        }
+                        moves.push((from, double, info));
+                    }
+                }
+            }
+        }
+        for to in squares(targets) {
+            let mut info = self.move_info(from, to, Normal);
+            if to.get_y() == last {
+                info.state_info.promoting = Some(to);
+            }
+            moves.push((from, to, info));
+        }
+
+        if let Some(to) = self.info.en_passant {
+            if pawn_attacks(from, self.turn) & bit(to) != 0 {
+                let captured = Position::from_xy(to.get_x(), from.get_y()).unwrap();
+                moves.push((from, to, self.move_info(from, to, EnPassant(captured))));
+            }
+        }
+    }
+
+    // The king and rook may castle once neither has moved, with every square
+    // either of them crosses or lands on empty apart from the two of them, and
+    // with the king out of check and not passing through an attacked square.
+    fn gen_castling_moves(&self, moves: &mut Vec<(Position, Position, MoveInfo)>, from: Position) {
+        if self.in_check() {
+            return;
+        }
+        let occupied = self.board.occupied();
+        let castle_info = self.move_info(from, from, Normal).state_info;
+        for side in 0..2 {
+            let right = castling_right(self.turn, side);
+            if !self.info.castling[right] {
+                continue;
+            }
+            let rook = self.rooks[right];
+            let (king_to, rook_to) = castled_squares(self.turn, side);
+            let path =
+                between(from, king_to) | bit(king_to) | between(rook, rook_to) | bit(rook_to);
+            if path & occupied & !bit(from) & !bit(rook) != 0
+                || squares(between(from, king_to))
+                    .any(|pos| self.board.is_attacked(pos, self.turn.flipped()))
+            {
+                continue;
+            }
+            // In Chess960 the king might not move at all, or move one square
+            // like an ordinary king move, so castling is written as the king
+            // taking its own rook.
+            let to = if self.chess960 { rook } else { king_to };
+            moves.push((
+                from,
+                to,
+                MoveInfo {
+                    move_type: Castle {
+                        rook,
+                        rook_to,
+                        king_to,
+                    },
+                    state_info: castle_info,
+                },
+            ));
+        }
+    }
+
+    fn move_info(&self, from: Position, to: Position, move_type: MoveType) -> MoveInfo {
+        let mut info = MoveInfo {
+            move_type,
+            ..Default::default()
+        };
+
+        if self.board.get(from).unwrap().variant == King {
+            info.state_info.castling[castling_right(self.turn, 0)] = false;
+            info.state_info.castling[castling_right(self.turn, 1)] = false;
+        }
+        for (right, &rook) in self.rooks.iter().enumerate() {
+            if from == rook || to == rook {
+                info.state_info.castling[right] = false;
+            }
+        }
+
+        info
+    }
+
+    pub(crate) fn lookup_move(&self, from: Position, to: Position) -> Option<MoveInfo> {
+        if self.info.promoting.is_some() {
+            return None;
+        }
+        self.moves
+            .iter()
+            .find(|&&(f, t, info)| f == from && t == to && !matches!(info.move_type, Drop(_)))
+            .map(|&(_, _, info)| info)
+    }
+
+    fn lookup_drop(&self, variant: Variant, to: Position) -> Option<MoveInfo> {
+        if self.info.promoting.is_some() {
+            return None;
+        }
+        self.moves
+            .iter()
+            .find(|&&(_, t, info)| {
+                t == to && matches!(info.move_type, Drop(piece) if piece.variant == variant)
+            })
+            .map(|&(_, _, info)| info)
+    }
+
+    fn lookup(&self, mv: Move) -> Option<MoveInfo> {
+        match mv.kind {
+            MoveKind::Drop(variant) => self.lookup_drop(variant, mv.to),
+            _ => self.lookup_move(mv.from, mv.to),
+        }
+    }
+
+    /// Drops a piece from the pocket of the side to move onto `to` if that is
+    /// legal, returning whether it was.
+    pub fn try_drop(&mut self, variant: Variant, to: Position) -> bool {
+        if let Some(info) = self.lookup_drop(variant, to) {
+            self.play(to, to, info);
+            self.end_turn();
+            return true;
+        }
+        false
+    }
+
+    /// Moves the piece on `from` to `to` if that is legal, returning whether it
+    /// was. If a pawn reaches the last rank the turn does not pass until
+    /// [`State::promote`] is called.
+    pub fn try_move(&mut self, from: Position, to: Position) -> bool {
+        if let Some(info) = self.lookup_move(from, to) {
+            self.play(from, to, info);
+            if self.info.promoting.is_none() {
+                self.end_turn();
+            } else {
+                // The pawn has moved, so the position is no longer the one
+                // the move was played from.
+                self.update_hash();
+            }
+            return true;
+        }
+        false
+    }
+
+    /// Plays a legal move, returning a record that [`State::unmake_move`] uses
+    /// to take it back. Returns `None` if the move is not legal.
+    pub fn make_move(&mut self, mv: Move) -> Option<Undo> {
+        if !self.is_legal(mv) {
+            return None;
+        }
+        let info = self.lookup(mv).unwrap();
+        let undo = self.make(mv.from, mv.to, info, mv.promotion);
+        self.gen_legal_moves();
+        Some(undo)
+    }
+
+    /// Takes back the move that produced `undo`. Moves must be taken back in
+    /// the reverse of the order they were made.
+    pub fn unmake_move(&mut self, undo: Undo) {
+        self.unmake(undo);
+        self.ended = None;
+        self.gen_legal_moves();
+    }
+
+    /// Whether moving the piece on `from` to `to` is legal.
+    pub fn is_valid_move(&self, from: Position, to: Position) -> bool {
+        self.lookup_move(from, to).is_some()
+    }
+
+    /// All legal moves, with one entry per promotion choice.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        if self.info.promoting.is_some() {
+            return moves;
+        }
+        for (from, to, info) in self.moves.iter() {
+            let (from, to) = (*from, *to);
+            if info.state_info.promoting.is_some() {
+                for &variant in self.rules.promotions() {
+                    moves.push(self.to_move(from, to, info, Some(variant)));
+                }
+            } else {
+                moves.push(self.to_move(from, to, info, None));
+            }
+        }
+        moves
+    }
+
+    /// Finds the legal move from `from` to `to`. `promotion` must be given
+    /// exactly when the move is a promotion.
+    pub fn find_move(
+        &self,
+        from: Position,
+        to: Position,
+        promotion: Option<Variant>,
+    ) -> Option<Move> {
+        let info = self.lookup_move(from, to)?;
+        match (info.state_info.promoting, promotion) {
+            (None, None) => Some(self.to_move(from, to, &info, None)),
+            (Some(_), Some(variant)) if self.rules.promotions().contains(&variant) => {
+                Some(self.to_move(from, to, &info, promotion))
+            }
+            _ => None,
+        }
+    }
+
+    /// Finds the legal drop of a piece from the pocket of the side to move
+    /// onto `to`.
+    pub fn find_drop(&self, variant: Variant, to: Position) -> Option<Move> {
+        let info = self.lookup_drop(variant, to)?;
+        Some(self.to_move(to, to, &info, None))
+    }
+
+    /// The squares a piece from the pocket of the side to move can be dropped
+    /// on.
+    pub fn drop_squares(&self, variant: Variant) -> HashSet<Position> {
+        self.moves
+            .iter()
+            .filter(
+                |&&(_, _, info)| matches!(info.move_type, Drop(piece) if piece.variant == variant),
+            )
+            .map(|&(_, to, _)| to)
+            .collect()
+    }
+
+    /// Whether `mv` is a legal move in this position.
+    pub fn is_legal(&self, mv: Move) -> bool {
+        let found = match mv.kind {
+            MoveKind::Drop(variant) => self.find_drop(variant, mv.to),
+            _ => self.find_move(mv.from, mv.to, mv.promotion),
+        };
+        found == Some(mv)
+    }
+
+    /// Plays a legal move including any promotion, returning whether it was
+    /// legal. A promotion is dropped without error if the pawn is blown up
+    /// in Atomic, so read the move rather than the board to find out what it
+    /// promoted to.
+    pub fn apply(&mut self, mv: Move) -> bool {
+        if !self.is_legal(mv) {
+            return false;
+        }
+        if let MoveKind::Drop(variant) = mv.kind {
+            return self.try_drop(variant, mv.to);
+        }
+        self.try_move(mv.from, mv.to);
+        if let Some(variant) = mv.promotion {
+            self.promote(variant);
+        }
+        true
+    }
+
+    pub(crate) fn to_move(
+        &self,
+        from: Position,
+        to: Position,
+        info: &MoveInfo,
+        promotion: Option<Variant>,
+    ) -> Move {
+        let (captured, kind) = match info.move_type {
+            Castle { .. } => (None, MoveKind::Castle),
+            Drop(piece) => (None, MoveKind::Drop(piece.variant)),
+            EnPassant(pos) => (self.board.get(pos), MoveKind::EnPassant),
+            Normal if info.state_info.en_passant.is_some() => (None, MoveKind::DoublePush),
+            Normal => (self.board.get(to), MoveKind::Normal),
+        };
+        Move {
+            from,
+            to,
+            promotion,
+            captured,
+            kind,
+        }
+    }
+
+    fn play(&mut self, from: Position, to: Position, info: MoveInfo) -> Undo {
+        let undo = Undo {
+            from,
+            to,
+            move_type: info.move_type,
+            captured: None,
+            promoted: false,
+            board: None,
+            info: self.info,
+            checks: self.checks,
+            pockets: self.pockets,
+            promoted_squares: self.promoted,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: self.hash,
+        };
+        let moving = match info.move_type {
+            Drop(piece) => piece,
+            _ => self.board.get(from).unwrap(),
+        };
+        if moving.variant == Pawn {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        let before = if self.rules.explosions() {
+            Some(self.board)
+        } else {
+            None
+        };
+        let captured = make_on(self.rules, &mut self.board, from, to, info.move_type);
+        self.info.apply(info.state_info);
+        if self.rules.drops() {
+            self.update_pockets(from, to, info.move_type, captured);
+        }
+        if captured.is_none() {
+            return undo;
+        }
+        self.halfmove_clock = 0;
+        if before.is_none() {
+            return Undo { captured, ..undo };
+        }
+        // The explosion may take rooks that could castle, and the promoting
+        // pawn along with everything else.
+        for right in 0..4 {
+            let rook = Piece {
+                colour: if right < 2 { White } else { Black },
+                variant: Rook,
+            };
+            if self.board.get(self.rooks[right]) != Some(rook) {
+                self.info.castling[right] = false;
+            }
+        }
+        self.info.promoting = None;
+        Undo {
+            captured,
+            board: before,
+            ..undo
+        }
+    }
+
+    // Puts a captured piece in the capturer's pocket, a promoted piece going
+    // back as a pawn, takes out a dropped piece, and moves the mark on a
+    // promoted piece along with it.
+    fn update_pockets(
+        &mut self,
+        from: Position,
+        to: Position,
+        move_type: MoveType,
+        captured: Option<Piece>,
+    ) {
+        let pocket = &mut self.pockets[self.turn as usize];
+        if let Some(piece) = captured {
+            let variant = if self.promoted & bit(to) != 0 {
+                Pawn
+            } else {
+                piece.variant
+            };
+            pocket[variant as usize] += 1;
+        }
+        match move_type {
+            Drop(piece) => pocket[piece.variant as usize] -= 1,
+            Castle { .. } => (),
+            Normal | EnPassant(_) => {
+                let moved = self.promoted & bit(from) != 0;
+                self.promoted &= !(bit(from) | bit(to));
+                if moved {
+                    self.promoted |= bit(to);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn make(
+        &mut self,
+        from: Position,
+        to: Position,
+        info: MoveInfo,
+        promotion: Option<Variant>,
+    ) -> Undo {
+        let mut undo = self.play(from, to, info);
+        if let (Some(variant), None) = (promotion, undo.board) {
+            self.board.set(
+                to,
+                Piece {
+                    colour: self.turn,
+                    variant,
+                },
+            );
+            self.info.promoting = None;
+            undo.promoted = true;
+            if self.rules.drops() {
+                self.promoted |= bit(to);
+            }
+        }
+        self.pass_turn();
+        undo
+    }
+
+    pub(crate) fn unmake(&mut self, undo: Undo) {
+        self.turn.flip();
+        if let Some(board) = undo.board {
+            self.board = board;
+        } else if undo.promoted {
+            self.board.set(
+                undo.to,
+                Piece {
+                    colour: self.turn,
+                    variant: Pawn,
+                },
+            );
+        }
+        if undo.board.is_none() {
+            undo.move_type
+                .unmake(&mut self.board, undo.from, undo.to, undo.captured);
+        }
+        self.info = undo.info;
+        self.checks = undo.checks;
+        self.pockets = undo.pockets;
+        self.promoted = undo.promoted_squares;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.hash = undo.hash;
+    }
+
+    /// Counts the leaf nodes of the move tree to the given depth.
+    pub fn perft(&self, depth: u32) -> u64 {
+        let mut state = self.clone();
+        state.perft_in_place(depth, &mut Vec::new())
+    }
+
+    fn perft_in_place(
+        &mut self,
+        depth: u32,
+        moves: &mut Vec<(Position, Position, MoveInfo)>,
+    ) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        if self.rules.outcome(self).is_some() {
+            return 0;
+        }
+        let start = moves.len();
+        self.gen_legal_into(moves);
+        let mut nodes = 0;
+        for i in start..moves.len() {
+            let (from, to, info) = moves[i];
+            for promotion in promotions(self.rules, info) {
+                if depth == 1 {
+                    nodes += 1;
+                } else {
+                    let undo = self.make(from, to, info, promotion);
+                    nodes += self.perft_in_place(depth - 1, moves);
+                    self.unmake(undo);
+                }
+            }
+        }
+        moves.truncate(start);
+        nodes
+    }
+
+    /// The perft count below each legal move, sorted by the move in UCI
+    /// notation.
+    pub fn divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        let mut divide = Vec::new();
+        if depth == 0 {
+            return divide;
+        }
+        let mut state = self.clone();
+        let mut moves = Vec::new();
+        for (from, to, info) in self.legal_move_infos() {
+            for promotion in promotions(self.rules, info) {
+                let undo = state.make(from, to, info, promotion);
+                divide.push((
+                    self.to_move(from, to, &info, promotion),
+                    state.perft_in_place(depth - 1, &mut moves),
+                ));
+                state.unmake(undo);
+            }
+        }
+        divide.sort_by_key(|(mv, _)| mv.to_string());
+        divide
+    }
+
+    fn pass_turn(&mut self) {
+        if self.turn == Black {
+            self.fullmove_number += 1;
+        }
+        self.turn.flip();
+        if self.rules.check_limit().is_some() && self.in_check() {
+            self.checks[self.turn.flipped() as usize] += 1;
+        }
+        self.update_hash();
+    }
+
+    pub(crate) fn update_hash(&mut self) {
+        let mut hash = self.board.hash();
+        if self.turn == Black {
+            hash ^= zobrist::side();
+        }
+        for (i, &right) in self.info.castling.iter().enumerate() {
+            if right {
+                hash ^= zobrist::castling(i);
+            }
+        }
+        if self.rules.drops() {
+            for &colour in [White, Black].iter() {
+                for &variant in [Pawn, Knight, Bishop, Rook, Queen].iter() {
+                    let count = self.pocket(colour, variant);
+                    if count > 0 {
+                        hash ^= zobrist::pocket(colour, variant, count);
+                    }
+                }
+            }
+        }
+        if self.rules.check_limit().is_some() {
+            for colour in [White, Black].iter() {
+                let checks = self.checks[*colour as usize];
+                if checks > 0 {
+                    hash ^= zobrist::checks(*colour, checks);
+                }
+            }
+        }
+        if let Some(pos) = self.info.en_passant {
+            if pawn_attacks(pos, self.turn.flipped()) & self.board.pieces(self.turn, Pawn) != 0 {
+                hash ^= zobrist::en_passant(pos.get_x());
+            }
+        }
+        self.hash = hash;
+    }
+
+    /// A Zobrist hash of the position. Positions that are the same for
+    /// repetition purposes have the same hash.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    fn end_turn(&mut self) {
+        self.pass_turn();
+        self.gen_legal_moves();
+    }
+
+    /// Completes a pending promotion, returning whether `variant` was a valid
+    /// choice.
+    pub fn promote(&mut self, variant: Variant) -> bool {
+        if let Some(pos) = self.info.promoting {
+            if self.rules.promotions().contains(&variant) {
+                self.board.set(
+                    pos,
+                    Piece {
+                        colour: self.turn,
+                        variant,
+                    },
+                );
+                if self.rules.drops() {
+                    self.promoted |= bit(pos);
+                }
+                self.end_turn();
+                self.info.promoting = None;
+                return true;
+            }
+        }
+        false
+    }
+
+    pub(crate) fn check_material(&self) -> bool {
+        if self.board.variant(Pawn) | self.board.variant(Rook) | self.board.variant(Queen) != 0 {
+            return true;
+        }
+        let minors = self.board.variant(Knight) | self.board.variant(Bishop);
+        (minors & self.board.colour(White)).count_ones() > 1
+            || (minors & self.board.colour(Black)).count_ones() > 1
+    }
+
+    /// Every piece on the board, by square.
+    pub fn get_pieces(&self) -> HashMap<Position, Piece> {
+        self.board.iter().collect()
+    }
+
+    /// The piece on a square, if any.
+    pub fn get_piece(&self, pos: Position) -> Option<Piece> {
+        self.board.get(pos)
+    }
+
+    /// The destination squares of each piece that can move.
+    pub fn get_piece_moves(&self) -> HashMap<Position, HashSet<Position>> {
+        let mut piece_moves = HashMap::new();
+        for &(from, to, info) in self.moves.iter() {
+            if let Drop(_) = info.move_type {
+                continue;
+            }
+            let piece = piece_moves.entry(from).or_insert(HashSet::new());
+            (*piece).insert(to);
+        }
+        piece_moves
+    }
+
+    /// The side to move.
+    pub fn get_turn(&self) -> Colour {
+        self.turn
+    }
+
+    /// Whether `colour` may still castle on the king's side, or on the
+    /// queen's side if `king_side` is false.
+    pub fn can_castle(&self, colour: Colour, king_side: bool) -> bool {
+        self.info.castling[castling_right(colour, if king_side { 0 } else { 1 })]
+    }
+
+    /// The square a pawn may capture onto en passant, if the last move was a
+    /// double pawn push.
+    pub fn en_passant(&self) -> Option<Position> {
+        self.info.en_passant
+    }
+
+    /// The square of the king of the side to move, if it is in check.
+    pub fn king_in_check(&self) -> Option<Position> {
+        if self.in_check() {
+            return self.board.king(self.turn);
+        }
+        None
+    }
+
+    pub(crate) fn in_check(&self) -> bool {
+        self.exposed(&self.board, self.turn)
+    }
+
+    /// Whether a promotion is waiting for [`State::promote`].
+    pub fn promoting(&self) -> bool {
+        self.info.promoting.is_some()
+    }
+
+    /// How the game ended, if it has.
+    pub fn ended(&self) -> &Option<EndState> {
+        &self.ended
+    }
+
+    /// Ends the game by threefold repetition. The position does not keep its
+    /// own history, so the caller decides when this applies.
+    pub fn threefold_repetition(&mut self) {
+        self.ended = Some(EndState::ThreefoldRepetition);
+        self.moves = Vec::new();
+    }
+
+    /// Ends the game because `colour` ran out of time. The opponent wins,
+    /// unless they have too little material left to ever checkmate.
+    pub fn flag_fall(&mut self, colour: Colour) {
+        let winner = colour.flipped();
+        self.ended = Some(if self.can_checkmate(winner) {
+            EndState::Timeout(winner)
+        } else {
+            EndState::TimeoutVsInsufficientMaterial
+        });
+        self.moves = Vec::new();
+    }
+
+    // A lone king can never checkmate, and a king with a single minor piece
+    // only can when the opponent has pieces of their own to block their king.
+    fn can_checkmate(&self, colour: Colour) -> bool {
+        let own = self.board.colour(colour);
+        let heavy = self.board.variant(Pawn) | self.board.variant(Rook) | self.board.variant(Queen);
+        if own & heavy != 0 {
+            return true;
+        }
+        let minors = own & (self.board.variant(Knight) | self.board.variant(Bishop));
+        match minors.count_ones() {
+            0 => false,
+            1 => self.board.colour(colour.flipped()).count_ones() > 1,
+            _ => true,
+        }
+    }
+
+    /// Halfmoves since the last capture or pawn move.
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    /// The number of the current full move, starting at 1.
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    /// Whether the side to move may claim a draw under the fifty-move rule.
+    pub fn can_claim_fifty_moves(&self) -> bool {
+        self.ended.is_none() && self.info.promoting.is_none() && self.halfmove_clock >= 100
+    }
+
+    /// Ends the game under the fifty-move rule if it can be claimed, returning
+    /// whether it was.
+    pub fn claim_fifty_moves(&mut self) -> bool {
+        if self.can_claim_fifty_moves() {
+            self.ended = Some(EndState::FiftyMoveRule);
+            self.moves = Vec::new();
+            return true;
+        }
+        false
+    }
+}
+
+/// What [`State::unmake_move`] needs to take back a move.
+#[derive(Clone, Copy)]
+pub struct Undo {
+    from: Position,
+    to: Position,
+    move_type: MoveType,
+    captured: Option<Piece>,
+    promoted: bool,
+    // The board before a capture that exploded.
+    board: Option<Board>,
+    info: StateInfo,
+    checks: [u32; 2],
+    pockets: [[u32; 5]; 2],
+    promoted_squares: Bitboard,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    hash: u64,
+}
+
+#[derive(Clone, Copy, Default)]
+pub(crate) struct MoveInfo {
+    pub(crate) state_info: StateInfo,
+    pub(crate) move_type: MoveType,
+}
+
+#[derive(Clone, Copy, Default)]
+pub(crate) enum MoveType {
+    #[default]
+    Normal,
+    Castle {
+        rook: Position,
+        rook_to: Position,
+        king_to: Position,
+    },
+    EnPassant(Position),
+    Drop(Piece),
+}
+
+impl MoveType {
+    fn make(&self, board: &mut Board, from: Position, to: Position) -> Option<Piece> {
+        let captured = match *self {
+            // The king may land where the rook stood or the other way around,
+            // so both are lifted before either is put down.
+            Castle {
+                rook,
+                rook_to,
+                king_to,
+            } => {
+                let king = board.remove(from).unwrap();
+                let rook = board.remove(rook).unwrap();
+                board.set(king_to, king);
+                board.set(rook_to, rook);
+                return None;
+            }
+            Drop(piece) => {
+                board.set(to, piece);
+                return None;
+            }
+            EnPassant(p) => board.remove(p),
+            Normal => board.remove(to),
+        };
+        board.move_piece(from, to);
+        captured
+    }
+
+    fn unmake(&self, board: &mut Board, from: Position, to: Position, captured: Option<Piece>) {
+        if let Castle {
+            rook,
+            rook_to,
+            king_to,
+        } = *self
+        {
+            let king = board.remove(king_to).unwrap();
+            let piece = board.remove(rook_to).unwrap();
+            board.set(from, king);
+            board.set(rook, piece);
+            return;
+        }
+        if let Drop(_) = *self {
+            board.remove(to);
+            return;
+        }
+        board.move_piece(to, from);
+        match *self {
+            Castle { .. } | Drop(_) => (),
+            EnPassant(p) => board.set(p, captured.unwrap()),
+            Normal => {
+                if let Some(piece) = captured {
+                    board.set(to, piece);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct StateInfo {
+    // Indexed by `castling_right`, in the order of the FEN field: white short,
+    // white long, black short, black long.
+    pub(crate) castling: [bool; 4],
+    pub(crate) en_passant: Option<Position>,
+    pub(crate) promoting: Option<Position>,
+}
+
+impl StateInfo {
+    fn apply(&mut self, other: StateInfo) {
+        for (right, &kept) in self.castling.iter_mut().zip(other.castling.iter()) {
+            *right &= kept;
+        }
+        self.en_passant = other.en_passant;
+        self.promoting = other.promoting;
+    }
+}
+
+impl Default for StateInfo {
+    fn default() -> Self {
+        StateInfo {
+            castling: [true; 4],
+            en_passant: None,
+            promoting: None,
+        }
+    }
+}
+
+impl PartialEq for State {
+    fn eq(&self, other: &State) -> bool {
+        self.board == other.board
+            && self.turn == other.turn
+            && self.info.castling == other.info.castling
+            && (0..4)
+                .all(|right| !self.info.castling[right] || self.rooks[right] == other.rooks[right])
+            && self.info.en_passant == other.info.en_passant
+            && self.checks == other.checks
+            && self.pockets == other.pockets
+            && self.rules.name() == other.rules.name()
+    }
+}
+impl Eq for State {}
+
+// Plays a move on `board`, exploding any capture if the rules say so, and
+// returns the piece captured.
+fn make_on(
+    rules: &dyn Rules,
+    board: &mut Board,
+    from: Position,
+    to: Position,
+    move_type: MoveType,
+) -> Option<Piece> {
+    let captured = move_type.make(board, from, to);
+    if captured.is_some() && rules.explosions() {
+        board.remove(to);
+        for pos in squares(king_attacks(to) & board.occupied() & !board.variant(Pawn)) {
+            board.remove(pos);
+        }
+    }
+    captured
+}
+
+// The promotion choices for a move, or just `None` if it is not a promotion.
+pub(crate) fn promotions(
+    rules: &dyn Rules,
+    info: MoveInfo,
+) -> impl Iterator<Item = Option<Variant>> {
+    let (choices, plain): (&[Variant], _) = match info.state_info.promoting {
+        Some(_) => (rules.promotions(), None),
+        None => (&[], Some(None)),
+    };
+    choices.iter().copied().map(Some).chain(plain)
+}
+
+// The castling rights of `colour`, with side 0 for short castling and 1 for
+// long.
+pub(crate) fn castling_right(colour: Colour, side: usize) -> usize {
+    colour as usize * 2 + side
+}
+
+// Where the king and rook end up after castling: the g and f files for short
+// castling and the c and d files for long, wherever they started.
+pub(crate) fn castled_squares(colour: Colour, side: usize) -> (Position, Position) {
+    let rank = if colour == White { 0 } else { 7 };
+    let (king, rook) = if side == 0 { (6, 5) } else { (2, 3) };
+    (
+        Position::from_xy(king, rank).unwrap(),
+        Position::from_xy(rook, rank).unwrap(),
+    )
+}
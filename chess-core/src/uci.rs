@@ -0,0 +1,617 @@
+use crate::state::MoveType::Castle;
+use crate::*;
+use std::error::Error;
+use std::ffi::OsStr;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// How long an engine is given to answer `uci` and `isready`.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl State {
+    /// Reads a move in the long algebraic notation used by UCI, e.g. `e2e4`,
+    /// `e7e8q` or the drop `N@f3`. Returns `None` if it is not a legal move
+    /// here.
+    ///
+    /// Castling may also be written as the king taking its own rook, as
+    /// engines do in their Chess960 mode.
+    pub fn parse_uci(&self, uci: &str) -> Option<Move> {
+        if !uci.is_ascii() || (uci.len() != 4 && uci.len() != 5) {
+            return None;
+        }
+        if &uci[1..2] == "@" && uci.len() == 4 {
+            let variant = Variant::from_char(uci.chars().next().unwrap())?;
+            return self.find_drop(variant, Position::parse(&uci[2..])?);
+        }
+        let from = Position::parse(&uci[0..2])?;
+        let to = Position::parse(&uci[2..4])?;
+        let promotion = match uci[4..].chars().next() {
+            Some(c) => Some(Variant::from_char(c.to_ascii_uppercase())?),
+            None => None,
+        };
+        self.find_move(from, to, promotion).or_else(|| {
+            let &(from, to, _) = self.moves.iter().find(|&&(f, _, info)| {
+                f == from && matches!(info.move_type, Castle { rook, .. } if rook == to)
+            })?;
+            self.find_move(from, to, promotion)
+        })
+    }
+}
+
+/// An option an engine offers, as declared in its reply to `uci`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineOption {
+    /// The name of the option.
+    pub name: String,
+    /// The type of the option and its default value.
+    pub kind: OptionKind,
+}
+
+/// The type of an [`EngineOption`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionKind {
+    /// On or off.
+    Check {
+        /// The default setting.
+        default: bool,
+    },
+    /// A whole number in a range.
+    Spin {
+        /// The default value.
+        default: i64,
+        /// The smallest value allowed.
+        min: i64,
+        /// The largest value allowed.
+        max: i64,
+    },
+    /// One of a list of choices.
+    Combo {
+        /// The default choice.
+        default: String,
+        /// Every choice.
+        choices: Vec<String>,
+    },
+    /// An action with no value, such as clearing the hash table.
+    Button,
+    /// Any text.
+    String {
+        /// The default text.
+        default: String,
+    },
+}
+
+/// What to search for with [`Engine::go`], written as the UCI `go` command.
+/// Anything left as `None` is not sent, and with nothing set the engine
+/// searches until it is told to stop.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Go {
+    /// The greatest depth to search to.
+    pub depth: Option<u32>,
+    /// The greatest number of positions to search.
+    pub nodes: Option<u64>,
+    /// The time to search for.
+    pub move_time: Option<Duration>,
+    /// The time left on White's and Black's clocks.
+    pub clock: Option<[Duration; 2]>,
+    /// The time White and Black gain with each move.
+    pub increment: Option<[Duration; 2]>,
+    /// The number of moves until the next time control.
+    pub moves_to_go: Option<u32>,
+}
+
+impl From<Limits> for Go {
+    fn from(limits: Limits) -> Go {
+        Go {
+            depth: limits.depth,
+            nodes: limits.nodes,
+            move_time: limits.time,
+            ..Default::default()
+        }
+    }
+}
+
+impl fmt::Display for Go {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "go")?;
+        if let Some([white, black]) = self.clock {
+            write!(
+                f,
+                " wtime {} btime {}",
+                white.as_millis(),
+                black.as_millis()
+            )?;
+        }
+        if let Some([white, black]) = self.increment {
+            write!(f, " winc {} binc {}", white.as_millis(), black.as_millis())?;
+        }
+        if let Some(moves) = self.moves_to_go {
+            write!(f, " movestogo {}", moves)?;
+        }
+        if let Some(depth) = self.depth {
+            write!(f, " depth {}", depth)?;
+        }
+        if let Some(nodes) = self.nodes {
+            write!(f, " nodes {}", nodes)?;
+        }
+        if let Some(time) = self.move_time {
+            write!(f, " movetime {}", time.as_millis())?;
+        }
+        if *self == Go::default() {
+            write!(f, " infinite")?;
+        }
+        Ok(())
+    }
+}
+
+/// A progress report from an engine's search. Anything the engine did not
+/// report is left empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Info {
+    /// The depth searched to.
+    pub depth: Option<u32>,
+    /// The greatest depth reached in any line.
+    pub selective_depth: Option<u32>,
+    /// Which of several best lines this is, starting at 1.
+    pub multi_pv: Option<u32>,
+    /// The score of the line, from the point of view of the side to move.
+    pub score: Option<Score>,
+    /// The number of positions searched.
+    pub nodes: Option<u64>,
+    /// The time spent searching.
+    pub time: Option<Duration>,
+    /// The best line found. Moves after the first one that is not legal are
+    /// left out.
+    pub pv: Vec<Move>,
+    /// Free text from the engine.
+    pub string: Option<String>,
+}
+
+/// Something an engine has sent, from [`Engine::poll`] or [`Engine::wait`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineEvent {
+    /// A progress report.
+    Info(Info),
+    /// The end of a search.
+    BestMove {
+        /// The move chosen, or `None` if there are no legal moves.
+        best: Option<Move>,
+        /// The reply the engine expects, if it gave one.
+        ponder: Option<Move>,
+    },
+}
+
+/// Something that went wrong talking to an engine.
+#[derive(Debug)]
+pub enum UciError {
+    /// The engine could not be started or written to.
+    Io(io::Error),
+    /// The engine has closed its output, usually because it has exited.
+    Exited,
+    /// The engine did not reply in time.
+    Timeout,
+    /// The engine has no option of this name.
+    UnknownOption(String),
+    /// A move, sent or received, that is not legal in its position.
+    IllegalMove(String),
+}
+
+impl fmt::Display for UciError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UciError::Io(error) => write!(f, "{}", error),
+            UciError::Exited => write!(f, "the engine has exited"),
+            UciError::Timeout => write!(f, "the engine did not reply in time"),
+            UciError::UnknownOption(name) => write!(f, "the engine has no option '{}'", name),
+            UciError::IllegalMove(mv) => write!(f, "illegal move '{}'", mv),
+        }
+    }
+}
+
+impl Error for UciError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            UciError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for UciError {
+    fn from(error: io::Error) -> UciError {
+        UciError::Io(error)
+    }
+}
+
+/// A chess engine running as a separate process and spoken to over the
+/// Universal Chess Interface.
+///
+/// The engine's output is read on a background thread, so [`Engine::poll`]
+/// never blocks and can be called once a frame. The engine is told to quit,
+/// and stopped if it does not, when this is dropped.
+///
+/// ```no_run
+/// use chess_core::{Engine, EngineEvent, Go, State};
+/// use std::time::Duration;
+///
+/// let mut engine = Engine::start("stockfish")?;
+/// engine.set_option("Hash", "64")?;
+/// engine.set_position(&State::new(), &[])?;
+/// engine.go(&Go {
+///     move_time: Some(Duration::from_millis(500)),
+///     ..Default::default()
+/// })?;
+/// loop {
+///     if let EngineEvent::BestMove { best, .. } = engine.wait(Duration::from_secs(5))? {
+///         println!("{:?}", best);
+///         break;
+///     }
+/// }
+/// # Ok::<(), chess_core::UciError>(())
+/// ```
+pub struct Engine {
+    child: Child,
+    input: ChildStdin,
+    // In a mutex only so that an engine can be shared between threads.
+    output: Mutex<Receiver<String>>,
+    name: Option<String>,
+    author: Option<String>,
+    options: Vec<EngineOption>,
+    position: State,
+}
+
+impl Engine {
+    /// Starts the engine at `path` and waits for it to introduce itself.
+    pub fn start(path: impl AsRef<OsStr>) -> Result<Engine, UciError> {
+        Engine::with_command(Command::new(path))
+    }
+
+    /// Starts an engine with a command that can have its own arguments and
+    /// working directory, and waits for it to introduce itself.
+    pub fn with_command(mut command: Command) -> Result<Engine, UciError> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let input = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, output) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = Engine {
+            child,
+            input,
+            output: Mutex::new(output),
+            name: None,
+            author: None,
+            options: Vec::new(),
+            position: State::new(),
+        };
+        engine.send("uci")?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        loop {
+            let line = engine.read_line(deadline)?;
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("id") => match words.next() {
+                    Some("name") => engine.name = Some(rest(&line, "name")),
+                    Some("author") => engine.author = Some(rest(&line, "author")),
+                    _ => (),
+                },
+                Some("option") => engine.options.extend(parse_option(&line)),
+                Some("uciok") => break,
+                _ => (),
+            }
+        }
+        engine.ready()?;
+        Ok(engine)
+    }
+
+    /// The name the engine gave, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The author the engine gave, if any.
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    /// The options the engine offers.
+    pub fn options(&self) -> &[EngineOption] {
+        &self.options
+    }
+
+    /// Sets one of the engine's options. The value is ignored for buttons.
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), UciError> {
+        let option = self
+            .options
+            .iter()
+            .find(|option| option.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| UciError::UnknownOption(name.to_string()))?;
+        let command = match option.kind {
+            OptionKind::Button => format!("setoption name {}", option.name),
+            _ => format!("setoption name {} value {}", option.name, value),
+        };
+        self.send(&command)
+    }
+
+    /// Tells the engine that the next position is from a different game.
+    pub fn new_game(&mut self) -> Result<(), UciError> {
+        self.send("ucinewgame")?;
+        self.ready()
+    }
+
+    /// Waits until the engine has dealt with everything sent so far. Anything
+    /// it sends in the meantime is discarded.
+    pub fn ready(&mut self) -> Result<(), UciError> {
+        self.send("isready")?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        while self.read_line(deadline)?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    /// Sets the position to search: the moves played from `start`. Engines
+    /// that offer `UCI_Chess960` are switched in and out of Chess960 mode to
+    /// match the position.
+    pub fn set_position(&mut self, start: &State, moves: &[Move]) -> Result<(), UciError> {
+        if start.is_chess960() != self.position.is_chess960()
+            && self
+                .options
+                .iter()
+                .any(|option| option.name == "UCI_Chess960")
+        {
+            self.send(&format!(
+                "setoption name UCI_Chess960 value {}",
+                start.is_chess960()
+            ))?;
+        }
+        let fen = start.to_fen();
+        let mut command = if fen == START {
+            "position startpos".to_string()
+        } else {
+            format!("position fen {}", fen)
+        };
+        let mut position = start.clone();
+        for (i, &mv) in moves.iter().enumerate() {
+            if !position.apply(mv) {
+                return Err(UciError::IllegalMove(mv.to_string()));
+            }
+            command.push_str(if i == 0 { " moves " } else { " " });
+            command.push_str(&mv.to_string());
+        }
+        self.send(&command)?;
+        self.position = position;
+        Ok(())
+    }
+
+    /// Starts searching the position.
+    pub fn go(&mut self, go: &Go) -> Result<(), UciError> {
+        self.send(&go.to_string())
+    }
+
+    /// Tells the engine to finish searching as soon as it can. It still
+    /// reports its best move.
+    pub fn stop(&mut self) -> Result<(), UciError> {
+        self.send("stop")
+    }
+
+    /// Returns the next thing the engine has sent, if there is one, without
+    /// waiting.
+    pub fn poll(&mut self) -> Result<Option<EngineEvent>, UciError> {
+        loop {
+            match self.output.get_mut().unwrap().try_recv() {
+                Ok(line) => {
+                    if let Some(event) = self.parse_event(&line)? {
+                        return Ok(Some(event));
+                    }
+                }
+                Err(TryRecvError::Empty) => return Ok(None),
+                Err(TryRecvError::Disconnected) => return Err(UciError::Exited),
+            }
+        }
+    }
+
+    /// Waits up to `timeout` for the next thing the engine sends.
+    pub fn wait(&mut self, timeout: Duration) -> Result<EngineEvent, UciError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let line = self.read_line(deadline)?;
+            if let Some(event) = self.parse_event(&line)? {
+                return Ok(event);
+            }
+        }
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), UciError> {
+        writeln!(self.input, "{}", command)?;
+        self.input.flush()?;
+        Ok(())
+    }
+
+    fn read_line(&mut self, deadline: Instant) -> Result<String, UciError> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        self.output
+            .get_mut()
+            .unwrap()
+            .recv_timeout(timeout)
+            .map_err(|error| match error {
+                RecvTimeoutError::Timeout => UciError::Timeout,
+                RecvTimeoutError::Disconnected => UciError::Exited,
+            })
+    }
+
+    // Lines other than `info` and `bestmove` are ignored.
+    fn parse_event(&self, line: &str) -> Result<Option<EngineEvent>, UciError> {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("info") => Ok(Some(EngineEvent::Info(self.parse_info(line)))),
+            Some("bestmove") => {
+                let best = match words.next() {
+                    Some("(none)") | Some("0000") | None => None,
+                    Some(uci) => Some(
+                        self.position
+                            .parse_uci(uci)
+                            .ok_or_else(|| UciError::IllegalMove(uci.to_string()))?,
+                    ),
+                };
+                let ponder = match (best, words.next(), words.next()) {
+                    (Some(best), Some("ponder"), Some(uci)) => {
+                        let mut position = self.position.clone();
+                        position.apply(best);
+                        position.parse_uci(uci)
+                    }
+                    _ => None,
+                };
+                Ok(Some(EngineEvent::BestMove { best, ponder }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn parse_info(&self, line: &str) -> Info {
+        let mut info = Info::default();
+        let mut words = line.split_whitespace().skip(1);
+        while let Some(word) = words.next() {
+            match word {
+                "depth" => info.depth = words.next().and_then(|n| n.parse().ok()),
+                "seldepth" => info.selective_depth = words.next().and_then(|n| n.parse().ok()),
+                "multipv" => info.multi_pv = words.next().and_then(|n| n.parse().ok()),
+                "nodes" => info.nodes = words.next().and_then(|n| n.parse().ok()),
+                "time" => {
+                    info.time = words
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .map(Duration::from_millis)
+                }
+                "score" => {
+                    let kind = words.next();
+                    let value = words.next().and_then(|n| n.parse().ok());
+                    info.score = match (kind, value) {
+                        (Some("cp"), Some(value)) => Some(Score::Centipawns(value)),
+                        (Some("mate"), Some(value)) => Some(Score::Mate(value)),
+                        _ => None,
+                    };
+                }
+                "pv" => {
+                    let mut position = self.position.clone();
+                    for uci in words.by_ref() {
+                        match position.parse_uci(uci) {
+                            Some(mv) => {
+                                position.apply(mv);
+                                info.pv.push(mv);
+                            }
+                            None => break,
+                        }
+                    }
+                }
+                "string" => {
+                    info.string = Some(words.collect::<Vec<&str>>().join(" "));
+                    break;
+                }
+                _ => (),
+            }
+        }
+        info
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + Duration::from_millis(500);
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// The text after `key` on a line, which may contain spaces.
+fn rest(line: &str, key: &str) -> String {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.iter().position(|&word| word == key) {
+        Some(i) => words[i + 1..].join(" "),
+        None => String::new(),
+    }
+}
+
+// Reads an `option name ... type ... default ...` line. Names, defaults and
+// choices may contain spaces, so each runs until the next keyword.
+fn parse_option(line: &str) -> Option<EngineOption> {
+    let mut name = Vec::new();
+    let mut kind = None;
+    let mut default = Vec::new();
+    let (mut min, mut max) = (None, None);
+    let mut choices: Vec<Vec<&str>> = Vec::new();
+    let mut key = "";
+    for word in line.split_whitespace().skip(1) {
+        match word {
+            "name" | "type" | "default" | "min" | "max" => key = word,
+            "var" => {
+                key = word;
+                choices.push(Vec::new());
+            }
+            _ => match key {
+                "name" => name.push(word),
+                "type" => kind = Some(word),
+                "default" => default.push(word),
+                "min" => min = word.parse().ok(),
+                "max" => max = word.parse().ok(),
+                "var" => choices.last_mut().unwrap().push(word),
+                _ => (),
+            },
+        }
+    }
+    let default = default.join(" ");
+    let kind = match kind? {
+        "check" => OptionKind::Check {
+            default: default == "true",
+        },
+        "spin" => OptionKind::Spin {
+            default: default.parse().ok()?,
+            min: min?,
+            max: max?,
+        },
+        "combo" => OptionKind::Combo {
+            default,
+            choices: choices.iter().map(|choice| choice.join(" ")).collect(),
+        },
+        "button" => OptionKind::Button,
+        "string" => OptionKind::String {
+            default: if default == "<empty>" {
+                String::new()
+            } else {
+                default
+            },
+        },
+        _ => return None,
+    };
+    if name.is_empty() {
+        return None;
+    }
+    Some(EngineOption {
+        name: name.join(" "),
+        kind,
+    })
+}
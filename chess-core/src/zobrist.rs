@@ -0,0 +1,51 @@
+use crate::*;
+
+const PIECES: usize = 0;
+const SIDE: usize = 768;
+const CASTLING: usize = 769;
+const EN_PASSANT: usize = 773;
+const CHECKS: usize = 781;
+const POCKETS: usize = 787;
+
+const fn generate() -> [u64; 947] {
+    let mut keys = [0; 947];
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut i = 0;
+    while i < keys.len() {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+const KEYS: [u64; 947] = generate();
+
+pub fn piece(pos: Position, piece: Piece) -> u64 {
+    KEYS[PIECES + (piece.colour as usize * 6 + piece.variant as usize) * 64 + pos.value as usize]
+}
+
+pub fn side() -> u64 {
+    KEYS[SIDE]
+}
+
+pub fn castling(right: usize) -> u64 {
+    KEYS[CASTLING + right]
+}
+
+pub fn en_passant(file: i8) -> u64 {
+    KEYS[EN_PASSANT + file as usize]
+}
+
+// Three counts per side is enough, as no variant plays on past three checks.
+pub fn checks(colour: Colour, count: u32) -> u64 {
+    KEYS[CHECKS + colour as usize * 3 + count.min(3) as usize - 1]
+}
+
+// Pockets rarely hold more than 16 of one piece, so larger counts share a key.
+pub fn pocket(colour: Colour, variant: Variant, count: u32) -> u64 {
+    KEYS[POCKETS + (colour as usize * 5 + variant as usize) * 16 + count.min(16) as usize - 1]
+}
@@ -0,0 +1,181 @@
+use chess_core::*;
+use std::collections::HashSet;
+
+fn perft(fen: &str, depth: u32) -> u64 {
+    State::from_fen(fen).unwrap().perft(depth)
+}
+
+fn pos(name: &str) -> Position {
+    Position::parse(name).unwrap()
+}
+
+#[test]
+fn start_positions() {
+    assert!(State::chess960(518).unwrap() == State::new());
+    assert!(!State::chess960(518).unwrap().is_chess960());
+    assert_eq!(
+        State::chess960(0).unwrap().to_fen(),
+        "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+    );
+    assert!(State::chess960(0).unwrap().is_chess960());
+    assert!(State::chess960(960).is_none());
+
+    let fens: HashSet<String> = (0..960)
+        .map(|n| State::chess960(n).unwrap().to_fen())
+        .collect();
+    assert_eq!(fens.len(), 960);
+    for fen in fens.iter() {
+        let back = fen.split('/').next().unwrap();
+        let file = |c: char| back.find(c).unwrap();
+        let bishops: Vec<usize> = back.match_indices('b').map(|(i, _)| i).collect();
+        assert_ne!(bishops[0] % 2, bishops[1] % 2, "{}", fen);
+        assert!(file('r') < file('k') && file('k') < back.rfind('r').unwrap());
+    }
+}
+
+#[test]
+fn castling_fields() {
+    // Shredder-FEN is read, and written back as X-FEN.
+    let state =
+        State::from_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9")
+            .unwrap();
+    assert!(state.is_chess960());
+    assert_eq!(
+        state.to_fen(),
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9"
+    );
+
+    // An inner rook needs its file.
+    let fen = "1r2k1rr/8/8/8/8/8/8/RR2K2R w KBgq - 0 1";
+    let state = State::from_fen(fen).unwrap();
+    assert_eq!(state.to_fen(), fen);
+    assert!(state.is_chess960());
+
+    let kind = |fen| State::from_fen(fen).err().unwrap().kind;
+    assert_eq!(
+        kind("4k3/8/8/8/8/8/8/4K3 w C - 0 1"),
+        FenErrorKind::InvalidCastling('C')
+    );
+    assert_eq!(
+        kind("4k3/8/8/8/8/8/8/4K2R w KH - 0 1"),
+        FenErrorKind::InvalidCastling('H')
+    );
+    assert_eq!(
+        kind("4k3/8/8/8/8/8/8/4K2R w I - 0 1"),
+        FenErrorKind::InvalidCharacter('I')
+    );
+}
+
+#[test]
+fn castling_moves() {
+    // The king is already on g1 and only the rook moves.
+    let mut state = State::from_fen("4k3/8/8/8/8/8/8/1R4KR w BH - 0 1").unwrap();
+    let short = state.parse_uci("g1h1").unwrap();
+    assert_eq!(short.kind, MoveKind::Castle);
+    assert_eq!(state.to_san(short).unwrap(), "O-O");
+    let long = state.parse_san("O-O-O").unwrap();
+    assert_eq!(long.to, pos("b1"));
+    assert!(state.apply(short));
+    assert_eq!(state.get_piece(pos("g1")).unwrap().variant, Variant::King);
+    assert_eq!(state.get_piece(pos("f1")).unwrap().variant, Variant::Rook);
+    assert_eq!(state.to_fen(), "4k3/8/8/8/8/8/8/1R3RK1 b - - 1 1");
+
+    // The king and rook swap places.
+    let mut state = State::from_fen("4k3/8/8/8/8/8/8/5RK1 w F - 0 1").unwrap();
+    let long = state.parse_san("O-O-O").unwrap();
+    assert!(state.apply(long));
+    assert_eq!(state.to_fen(), "4k3/8/8/8/8/8/8/2KR4 b - - 1 1");
+
+    // Pieces in the rook's way block castling even when the king's path is
+    // clear, and the king may not pass through an attacked square.
+    let state = State::from_fen("4k3/8/8/8/8/8/8/RN2K3 w A - 0 1").unwrap();
+    assert_eq!(state.parse_san("O-O-O"), Err(SanError::Illegal));
+    let state = State::from_fen("3rk3/8/8/8/8/8/8/1R3K2 w B - 0 1").unwrap();
+    assert_eq!(state.parse_san("O-O-O"), Err(SanError::Illegal));
+}
+
+#[test]
+fn rook_moves_lose_rights() {
+    let mut state = State::from_fen("1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1").unwrap();
+    for san in ["Rb2", "Rg7"].iter() {
+        let mv = state.parse_san(san).unwrap();
+        state.apply(mv);
+    }
+    assert_eq!(state.to_fen(), "1r2k3/6r1/8/8/8/8/1R6/4K1R1 w Kq - 2 2");
+}
+
+#[test]
+fn uci_castling() {
+    // Engines in Chess960 mode castle by taking their own rook, even in
+    // standard chess.
+    let state = State::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    let castle = state.parse_uci("e1h1").unwrap();
+    assert_eq!(castle, state.parse_uci("e1g1").unwrap());
+    assert_eq!(castle.to_string(), "e1g1");
+
+    let state = State::from_fen("4k3/8/8/8/8/8/8/5KR1 w G - 0 1").unwrap();
+    assert_eq!(state.parse_uci("f1g1").unwrap().kind, MoveKind::Castle);
+
+    // Either form can be asked for whatever the position.
+    let mut state = State::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    state.set_chess960(true);
+    assert_eq!(state.parse_uci("e1h1").unwrap().to_string(), "e1h1");
+    assert!(state.parse_uci("e1g1").is_none());
+    let mut state = State::from_fen("4k3/8/8/8/8/8/8/1R2K3 w B - 0 1").unwrap();
+    assert!(state.is_chess960());
+    state.set_chess960(false);
+    assert_eq!(state.parse_san("O-O-O").unwrap().to_string(), "e1c1");
+    assert_eq!(state.parse_uci("e1b1"), state.parse_uci("e1c1"));
+}
+
+#[test]
+fn perft_chess960() {
+    let fen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+    assert_eq!(perft(fen, 1), 21);
+    assert_eq!(perft(fen, 2), 528);
+    assert_eq!(perft(fen, 3), 12189);
+    assert_eq!(perft(fen, 4), 326672);
+
+    let fen = "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9";
+    assert_eq!(perft(fen, 1), 21);
+    assert_eq!(perft(fen, 2), 807);
+    assert_eq!(perft(fen, 3), 18002);
+    assert_eq!(perft(fen, 4), 667366);
+
+    let fen = "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9";
+    assert_eq!(perft(fen, 1), 20);
+    assert_eq!(perft(fen, 2), 479);
+    assert_eq!(perft(fen, 3), 10471);
+    assert_eq!(perft(fen, 4), 273318);
+
+    let fen = "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9";
+    assert_eq!(perft(fen, 1), 22);
+    assert_eq!(perft(fen, 2), 593);
+    assert_eq!(perft(fen, 3), 13440);
+    assert_eq!(perft(fen, 4), 382958);
+
+    let fen = "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9";
+    assert_eq!(perft(fen, 1), 28);
+    assert_eq!(perft(fen, 2), 1120);
+    assert_eq!(perft(fen, 3), 31058);
+    assert_eq!(perft(fen, 4), 1171749);
+}
+
+#[test]
+fn pgn_round_trip() {
+    let start = State::from_fen("1r4kr/8/8/8/8/8/8/1R4KR w BHbh - 0 1").unwrap();
+    let mut game = Game::new(start.clone());
+    let mut state = start;
+    for san in ["O-O", "O-O"].iter() {
+        let mv = state.parse_san(san).unwrap();
+        state.apply(mv);
+        game.moves.push(Node::new(mv));
+    }
+    let pgn = game.to_pgn().unwrap();
+    assert!(pgn.contains("[Variant \"Chess960\"]"));
+    assert!(pgn.contains("[FEN \"1r4kr/8/8/8/8/8/8/1R4KR w KQkq - 0 1\"]"));
+    assert!(pgn.contains("1. O-O O-O"));
+    let read = Game::from_pgn(&pgn).unwrap();
+    assert!(read.end_state().unwrap() == state);
+    assert_eq!(state.to_fen(), "1r3rk1/8/8/8/8/8/8/1R3RK1 w - - 2 2");
+}
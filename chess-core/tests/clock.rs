@@ -0,0 +1,105 @@
+use chess_core::{Colour::*, *};
+use std::time::Duration;
+
+fn secs(s: u64) -> Duration {
+    Duration::from_secs(s)
+}
+
+#[test]
+fn parses_and_writes_time_controls() {
+    for text in ["300", "180+2", "600d5", "900b10", "40/5400+30:1800+30"].iter() {
+        assert_eq!(TimeControl::parse(text).unwrap().to_string(), *text);
+    }
+    assert_eq!(
+        TimeControl::parse("40/5400:1800").unwrap().stages,
+        vec![
+            Stage {
+                moves: Some(40),
+                time: secs(5400),
+                bonus: Bonus::None,
+            },
+            Stage {
+                moves: None,
+                time: secs(1800),
+                bonus: Bonus::None,
+            },
+        ]
+    );
+    assert!(TimeControl::parse("").is_none());
+    assert!(TimeControl::parse("0/300").is_none());
+    assert!(TimeControl::parse("300+x").is_none());
+}
+
+#[test]
+fn starts_on_first_move() {
+    let mut clock = Clock::new(TimeControl::sudden_death(secs(60)));
+    assert_eq!(clock.tick(secs(100)), None);
+    assert_eq!(clock.remaining(White), secs(60));
+    clock.press(White);
+    assert!(clock.started());
+    assert_eq!(clock.turn(), Black);
+    clock.tick(secs(10));
+    assert_eq!(clock.remaining(Black), secs(50));
+    assert_eq!(clock.remaining(White), secs(60));
+}
+
+#[test]
+fn flag_falls_when_time_runs_out() {
+    let mut clock = Clock::new(TimeControl::sudden_death(secs(60)));
+    clock.press(White);
+    assert_eq!(clock.tick(secs(60)), None);
+    assert_eq!(clock.tick(Duration::from_millis(1)), Some(Black));
+    assert_eq!(clock.remaining(Black), secs(0));
+    clock.press(Black);
+    assert_eq!(clock.turn(), Black);
+}
+
+#[test]
+fn fischer_increment_is_added_after_each_move() {
+    let mut clock = Clock::new(TimeControl::fischer(secs(60), secs(2)));
+    clock.press(White);
+    clock.tick(secs(5));
+    clock.press(Black);
+    assert_eq!(clock.remaining(Black), secs(57));
+}
+
+#[test]
+fn bronstein_delay_gives_back_time_used() {
+    let mut clock = Clock::new(TimeControl::bronstein(secs(60), secs(5)));
+    clock.press(White);
+    clock.tick(secs(3));
+    clock.press(Black);
+    assert_eq!(clock.remaining(Black), secs(60));
+    clock.tick(secs(8));
+    assert_eq!(clock.remaining(White), secs(52));
+    clock.press(White);
+    assert_eq!(clock.remaining(White), secs(57));
+}
+
+#[test]
+fn simple_delay_holds_the_clock() {
+    let mut clock = Clock::new(TimeControl::delay(secs(60), secs(5)));
+    clock.press(White);
+    clock.tick(secs(4));
+    assert_eq!(clock.remaining(Black), secs(60));
+    clock.tick(secs(3));
+    assert_eq!(clock.remaining(Black), secs(58));
+    clock.press(Black);
+    assert_eq!(clock.remaining(Black), secs(58));
+}
+
+#[test]
+fn stages_add_time_after_their_moves() {
+    let mut clock = Clock::new(TimeControl::parse("2/60:30+1").unwrap());
+    clock.press(White);
+    clock.press(Black);
+    clock.tick(secs(10));
+    clock.press(White);
+    assert_eq!(clock.remaining(White), secs(80));
+    clock.tick(secs(10));
+    clock.press(Black);
+    assert_eq!(clock.remaining(Black), secs(80));
+    clock.tick(secs(10));
+    clock.press(White);
+    assert_eq!(clock.remaining(White), secs(71));
+}
@@ -0,0 +1,152 @@
+use chess_core::*;
+
+fn zh(fen: &str) -> State {
+    State::from_fen_with_rules(fen, &Crazyhouse).unwrap()
+}
+
+fn pos(name: &str) -> Position {
+    Position::parse(name).unwrap()
+}
+
+fn play(state: &mut State, moves: &[&str]) {
+    for uci in moves {
+        let mv = state.parse_uci(uci).unwrap();
+        assert!(state.apply(mv), "{}", uci);
+    }
+}
+
+#[test]
+fn perft_drops() {
+    assert_eq!(State::with_rules(&Crazyhouse).perft(4), 197281);
+    let state = zh("2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1");
+    assert_eq!(state.perft(1), 301);
+    assert_eq!(state.perft(2), 75353);
+}
+
+#[test]
+fn pockets_in_fen() {
+    let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R[Pp] w KQkq - 2 3";
+    let state = zh(fen);
+    assert_eq!(state.to_fen(), fen);
+    assert_eq!(state.pocket(Colour::White, Variant::Pawn), 1);
+    assert_eq!(state.pocket(Colour::White, Variant::Knight), 0);
+
+    let fen = "4k3/8/8/8/8/8/8/Q~3K3[RRnp] w - - 0 1";
+    assert_eq!(
+        State::from_fen_with_rules(fen, &Crazyhouse)
+            .unwrap()
+            .to_fen(),
+        fen
+    );
+
+    let kind = |fen| {
+        State::from_fen_with_rules(fen, &Crazyhouse)
+            .err()
+            .unwrap()
+            .kind
+    };
+    assert_eq!(
+        kind("4k3/8/8/8/8/8/8/4K3[Kq] w - - 0 1"),
+        FenErrorKind::InvalidCharacter('K')
+    );
+    assert_eq!(
+        kind("4k3/8/8/8/8/8/8/4K3[q w - - 0 1"),
+        FenErrorKind::Missing
+    );
+    assert_eq!(
+        kind("4k3/8/8/8/8/8/8/~4K3[] w - - 0 1"),
+        FenErrorKind::InvalidCharacter('~')
+    );
+    // Without drops, pockets are not part of the notation.
+    assert!(State::from_fen("4k3/8/8/8/8/8/8/4K3[] w - - 0 1").is_err());
+}
+
+#[test]
+fn captures_fill_pockets() {
+    let mut state = State::with_rules(&Crazyhouse);
+    play(&mut state, &["e2e4", "d7d5", "e4d5", "d8d5"]);
+    assert_eq!(state.pocket(Colour::White, Variant::Pawn), 1);
+    assert_eq!(state.pocket(Colour::Black, Variant::Pawn), 1);
+
+    let drop = state.parse_uci("P@e6").unwrap();
+    assert_eq!(drop.kind, MoveKind::Drop(Variant::Pawn));
+    assert_eq!(drop.to_string(), "P@e6");
+    assert_eq!(state.to_san(drop).unwrap(), "P@e6");
+    assert_eq!(state.parse_san("@e6"), Ok(drop));
+    assert!(state.apply(drop));
+    assert_eq!(state.pocket(Colour::White, Variant::Pawn), 0);
+    assert_eq!(
+        state.to_fen(),
+        "rnb1kbnr/ppp1pppp/4P3/3q4/8/8/PPPP1PPP/RNBQKBNR[p] b KQkq - 0 3"
+    );
+}
+
+#[test]
+fn drop_rules() {
+    // Pawns may not be dropped on the first or last rank.
+    let state = zh("4k3/8/8/8/8/8/8/4K3[PN] w - - 0 1");
+    assert!(state.find_drop(Variant::Pawn, pos("a8")).is_none());
+    assert!(state.find_drop(Variant::Pawn, pos("a1")).is_none());
+    assert!(state.find_drop(Variant::Knight, pos("a8")).is_some());
+    assert_eq!(state.drop_squares(Variant::Pawn).len(), 48);
+    assert!(state.drop_squares(Variant::Queen).is_empty());
+
+    // In check a drop must block.
+    let state = zh("4k3/8/8/8/8/8/8/r3K3[N] w - - 0 1");
+    let drops: Vec<Move> = state
+        .legal_moves()
+        .into_iter()
+        .filter(|mv| matches!(mv.kind, MoveKind::Drop(_)))
+        .collect();
+    assert_eq!(drops.len(), 3);
+
+    // A drop can deliver checkmate.
+    let mut state = zh("6rk/6pp/8/8/8/8/8/4K3[N] w - - 0 1");
+    play(&mut state, &["N@f7"]);
+    assert_eq!(state.ended(), &Some(EndState::Checkmate(Colour::White)));
+}
+
+#[test]
+fn promoted_pieces_return_as_pawns() {
+    let mut state = zh("7r/1P6/8/7k/8/8/8/K7 w - - 0 1");
+    play(&mut state, &["b7b8q"]);
+    assert_eq!(state.to_fen(), "1Q~5r/8/8/7k/8/8/8/K7[] b - - 0 1");
+    play(&mut state, &["h8b8"]);
+    assert_eq!(state.to_fen(), "1r6/8/8/7k/8/8/8/K7[p] w - - 0 2");
+}
+
+#[test]
+fn unmake_restores_pockets() {
+    let mut state = zh("4k3/8/8/8/8/8/r7/R3K3 w - - 0 1");
+    let before = state.clone();
+    let mut undos = Vec::new();
+    for uci in ["a1a2", "e8d7", "R@d1"].iter() {
+        let mv = state.parse_uci(uci).unwrap();
+        undos.push(state.make_move(mv).unwrap());
+    }
+    assert_eq!(state.to_fen(), "8/3k4/8/8/8/8/R7/3RK3[] b - - 2 2");
+    while let Some(undo) = undos.pop() {
+        state.unmake_move(undo);
+    }
+    assert!(state == before);
+    assert_eq!(state.hash(), before.hash());
+    assert_eq!(state.pocket(Colour::White, Variant::Rook), 0);
+}
+
+#[test]
+fn pgn_round_trip() {
+    let start = State::with_rules(&Crazyhouse);
+    let mut game = Game::new(start.clone());
+    let mut state = start;
+    for uci in ["e2e4", "d7d5", "e4d5", "d8d5", "P@e6"].iter() {
+        let mv = state.parse_uci(uci).unwrap();
+        state.apply(mv);
+        game.moves.push(Node::new(mv));
+    }
+    let pgn = game.to_pgn().unwrap();
+    assert!(pgn.contains("[Variant \"Crazyhouse\"]"));
+    assert!(!pgn.contains("[FEN"));
+    assert!(pgn.contains("3. P@e6"));
+    let read = Game::from_pgn(&pgn).unwrap();
+    assert!(read.end_state().unwrap() == state);
+}
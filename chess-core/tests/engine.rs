@@ -0,0 +1,144 @@
+use chess_core::*;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn start() -> Engine {
+    Engine::start(env!("CARGO_BIN_EXE_engine")).unwrap()
+}
+
+// Waits for the search to finish, returning the last report and the move.
+fn best_move(engine: &mut Engine) -> (Info, Option<Move>) {
+    let mut last = Info::default();
+    loop {
+        match engine.wait(Duration::from_secs(10)).unwrap() {
+            EngineEvent::Info(info) => last = info,
+            EngineEvent::BestMove { best, .. } => return (last, best),
+        }
+    }
+}
+
+#[test]
+fn handshake() {
+    let engine = start();
+    assert_eq!(engine.name(), Some("chess-core 0.1.0"));
+    let names: Vec<&str> = engine
+        .options()
+        .iter()
+        .map(|option| option.name.as_str())
+        .collect();
+    assert_eq!(names, ["Hash", "Clear Hash", "EvalFile", "UCI_Chess960"]);
+}
+
+#[test]
+fn finds_mate() {
+    let mut engine = start();
+    engine.set_option("Hash", "4").unwrap();
+    let start = State::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    engine.set_position(&start, &[]).unwrap();
+    engine
+        .go(&Go {
+            depth: Some(3),
+            ..Default::default()
+        })
+        .unwrap();
+    let (info, best) = best_move(&mut engine);
+    assert_eq!(best.unwrap().to_string(), "a1a8");
+    assert_eq!(info.score, Some(Score::Mate(1)));
+    assert_eq!(info.pv, [best.unwrap()]);
+}
+
+#[test]
+fn plays_from_moves() {
+    let mut engine = start();
+    engine.new_game().unwrap();
+    let start = State::new();
+    let mut state = start.clone();
+    let mut moves = Vec::new();
+    for san in ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"].iter() {
+        let mv = state.parse_san(san).unwrap();
+        state.apply(mv);
+        moves.push(mv);
+    }
+    engine.set_position(&start, &moves).unwrap();
+    engine
+        .go(&Go {
+            nodes: Some(2000),
+            ..Default::default()
+        })
+        .unwrap();
+    // The client only accepts moves that are legal after the moves sent.
+    let (_, best) = best_move(&mut engine);
+    assert!(state.is_legal(best.unwrap()));
+}
+
+#[test]
+fn no_move_when_the_game_is_over() {
+    let mut engine = start();
+    let mated = State::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").unwrap();
+    engine.set_position(&mated, &[]).unwrap();
+    engine.go(&Go::default()).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    engine.stop().unwrap();
+    assert_eq!(best_move(&mut engine).1, None);
+}
+
+#[test]
+fn stops_an_infinite_search() {
+    let mut engine = start();
+    engine.set_position(&State::new(), &[]).unwrap();
+    engine.go(&Go::default()).unwrap();
+    thread::sleep(Duration::from_millis(200));
+    let stopped = Instant::now();
+    engine.stop().unwrap();
+    let (info, best) = best_move(&mut engine);
+    assert!(best.is_some());
+    assert!(info.depth.unwrap() >= 1);
+    assert!(stopped.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn plays_on_the_clock() {
+    let mut engine = start();
+    engine.set_position(&State::new(), &[]).unwrap();
+    let started = Instant::now();
+    engine
+        .go(&Go {
+            clock: Some([Duration::from_millis(1500), Duration::from_secs(60)]),
+            increment: Some([Duration::from_millis(100); 2]),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(best_move(&mut engine).1.is_some());
+    assert!(started.elapsed() < Duration::from_millis(1000));
+}
+
+#[test]
+fn follows_chess960_option() {
+    let mut engine = start();
+    let start = State::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    let castle = start.parse_uci("e1g1").unwrap();
+    // In Chess960 mode castling is the king taking its own rook, so the
+    // standard form is not a legal move.
+    engine.set_option("UCI_Chess960", "true").unwrap();
+    engine.set_position(&start, &[castle]).unwrap();
+    match engine.wait(Duration::from_secs(5)).unwrap() {
+        EngineEvent::Info(info) => assert_eq!(info.string.unwrap(), "illegal move 'e1g1'"),
+        event => panic!("unexpected {:?}", event),
+    }
+}
+
+#[test]
+fn reports_bad_options() {
+    let mut engine = start();
+    engine
+        .set_option("EvalFile", "/nonexistent/eval.txt")
+        .unwrap();
+    match engine.wait(Duration::from_secs(5)).unwrap() {
+        EngineEvent::Info(info) => {
+            assert!(info.string.unwrap().starts_with("/nonexistent/eval.txt: "))
+        }
+        event => panic!("unexpected {:?}", event),
+    }
+    // The engine still works.
+    engine.ready().unwrap();
+}
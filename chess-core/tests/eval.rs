@@ -0,0 +1,113 @@
+use chess_core::*;
+
+fn evaluate(fen: &str) -> i32 {
+    Params::default().evaluate(&State::from_fen(fen).unwrap())
+}
+
+#[test]
+fn start_position_is_level() {
+    assert_eq!(Params::default().evaluate(&State::new()), 0);
+}
+
+#[test]
+fn mirrored_positions_are_equal() {
+    // The same position with the colours swapped and the board turned over.
+    let pairs = [
+        (
+            "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+            "rnbqk2r/pppp1ppp/5n2/2b1p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R b KQkq - 4 4",
+        ),
+        (
+            "8/5k2/3p4/1p1P4/1P6/4K3/8/8 w - - 0 1",
+            "8/8/4k3/1p6/1P1p4/3P4/5K2/8 b - - 0 1",
+        ),
+    ];
+    for &(white, black) in pairs.iter() {
+        assert_eq!(evaluate(white), evaluate(black));
+    }
+}
+
+#[test]
+fn scores_are_from_the_side_to_move() {
+    let white = evaluate("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+    let black = evaluate("4k3/8/8/8/8/8/8/3QK3 b - - 0 1");
+    assert!(white > 800);
+    assert_eq!(white, -black);
+}
+
+#[test]
+fn trace_agrees_with_evaluate() {
+    let params = Params::default();
+    let weights: Vec<[f64; 2]> = params
+        .weights()
+        .iter()
+        .map(|&[middlegame, endgame]| [middlegame as f64, endgame as f64])
+        .collect();
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    ]
+    .iter()
+    {
+        let state = State::from_fen(fen).unwrap();
+        let traced = Trace::new(&state).evaluate(&weights);
+        assert!((traced - params.evaluate(&state) as f64).abs() < 1.0);
+    }
+}
+
+#[test]
+fn parameters_round_trip() {
+    let mut params = Params::default();
+    params.weights_mut()[3] = [-7, 12];
+    assert_eq!(Params::parse(&params.to_string()), Ok(params));
+}
+
+#[test]
+fn parse_overrides_only_given_keys() {
+    let params = Params::parse(
+        "# Stronger passed pawns.\n\
+         pawn.passed = 0 10/20 20/40\n\
+         \x20   30/60 40/80 50/100 60/120 0\n",
+    )
+    .unwrap();
+    let default = Params::default();
+    let changed: Vec<usize> = (0..default.weights().len())
+        .filter(|&i| params.weights()[i] != default.weights()[i])
+        .collect();
+    assert_eq!(changed.len(), 6);
+    assert!(params
+        .to_string()
+        .contains("pawn.passed = 0 10/20 20/40 30/60"));
+}
+
+#[test]
+fn parse_errors() {
+    let error = |text| Params::parse(text).unwrap_err();
+    assert_eq!(
+        error("value.pawn = 100\nvalue.pawnn = 90"),
+        ParamsError {
+            line: 2,
+            kind: ParamsErrorKind::UnknownKey("value.pawnn".to_string()),
+        }
+    );
+    assert_eq!(error("\n100/90").kind, ParamsErrorKind::MissingKey);
+    assert_eq!(
+        error("bishop.pair = 30/x").kind,
+        ParamsErrorKind::InvalidValue("30/x".to_string())
+    );
+    assert_eq!(
+        error("table.rook = 1 2 3\n4 5\n\nvalue.rook = 500"),
+        ParamsError {
+            line: 1,
+            kind: ParamsErrorKind::WrongLength {
+                expected: 64,
+                found: 5,
+            },
+        }
+    );
+    assert_eq!(
+        error("mobility.queen = 1 2").to_string(),
+        "line 1: wrong number of values, expected 1 but found 2"
+    );
+}
@@ -0,0 +1,132 @@
+use chess_core::*;
+
+#[test]
+fn round_trip() {
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w Kq d6 0 3",
+        "4k3/8/8/8/8/8/R7/4K3 b - - 87 112",
+    ]
+    .iter()
+    {
+        assert_eq!(State::from_fen(fen).unwrap().to_fen(), *fen);
+    }
+}
+
+#[test]
+fn round_trip_castling_en_passant_and_clocks() {
+    for fen in [
+        "r3k2r/8/8/8/8/8/8/R3K2R w K - 0 1",
+        "r3k2r/8/8/8/8/8/8/R3K2R b Qk - 3 20",
+        "r3k2r/8/8/8/8/8/8/R3K2R w Kq - 12 41",
+        "r3k2r/8/8/8/8/8/8/R3K2R b KQq - 1 7",
+        "r3k2r/8/8/8/8/8/8/R3K2R w q - 49 100",
+        "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 2",
+        "rnbqkbnr/pp1ppppp/8/1Pp5/8/8/P1PPPPPP/RNBQKBNR w Qk c6 0 9",
+    ]
+    .iter()
+    {
+        assert_eq!(State::from_fen(fen).unwrap().to_fen(), *fen);
+    }
+}
+
+#[test]
+fn clocks_default_when_missing() {
+    let state = State::from_fen("4k3/8/8/8/8/8/8/4K3 w - -").unwrap();
+    assert_eq!(state.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+}
+
+#[test]
+fn errors_name_field_and_position() {
+    let cases = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1",
+            FenField::Placement,
+            42,
+            FenErrorKind::InvalidCharacter('X'),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN w KQkq - 0 1",
+            FenField::Placement,
+            42,
+            FenErrorKind::BadRankLength,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+            FenField::Placement,
+            34,
+            FenErrorKind::TooFewRanks,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            FenField::Turn,
+            44,
+            FenErrorKind::InvalidCharacter('x'),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkz - 0 1",
+            FenField::Castling,
+            49,
+            FenErrorKind::InvalidCharacter('z'),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1",
+            FenField::EnPassant,
+            51,
+            FenErrorKind::InvalidEnPassant,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1",
+            FenField::HalfmoveClock,
+            53,
+            FenErrorKind::InvalidNumber,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 3",
+            FenField::FullmoveNumber,
+            57,
+            FenErrorKind::TooManyFields,
+        ),
+        (
+            "4k3/8/8/8/8/8/8/4K3 w",
+            FenField::Castling,
+            21,
+            FenErrorKind::Missing,
+        ),
+    ];
+    for &(fen, field, index, kind) in cases.iter() {
+        let error = State::from_fen(fen).err().unwrap();
+        assert_eq!(error, FenError { field, index, kind }, "{}", fen);
+    }
+}
+
+#[test]
+fn rejects_illegal_setups() {
+    let kind = |fen| State::from_fen(fen).err().unwrap().kind;
+    assert_eq!(
+        kind("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQQBNR b kq - 0 1"),
+        FenErrorKind::MissingKing(Colour::White)
+    );
+    assert_eq!(
+        kind("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBKKBNR b kq - 0 1"),
+        FenErrorKind::ExtraKing(Colour::White)
+    );
+    assert_eq!(
+        kind("Pnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQk - 0 1"),
+        FenErrorKind::PawnOnBackRank(Position::parse("a8").unwrap())
+    );
+    assert_eq!(
+        kind("4k3/4R3/8/8/8/8/8/4K3 w - - 0 1"),
+        FenErrorKind::OpponentInCheck
+    );
+    assert_eq!(
+        kind("rnbqkbn1/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+        FenErrorKind::InvalidCastling('k')
+    );
+    assert_eq!(
+        kind("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQ1BNR w q - 0 1"),
+        FenErrorKind::MissingKing(Colour::White)
+    );
+}
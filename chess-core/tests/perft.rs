@@ -0,0 +1,122 @@
+use chess_core::*;
+
+fn perft(fen: &str, depth: u32) -> u64 {
+    State::from_fen(fen).unwrap().perft(depth)
+}
+
+#[test]
+fn perft_startpos() {
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(perft(fen, 1), 20);
+    assert_eq!(perft(fen, 2), 400);
+    assert_eq!(perft(fen, 3), 8902);
+    assert_eq!(perft(fen, 4), 197281);
+}
+
+#[test]
+fn perft_kiwipete() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    assert_eq!(perft(fen, 1), 48);
+    assert_eq!(perft(fen, 2), 2039);
+    assert_eq!(perft(fen, 3), 97862);
+}
+
+#[test]
+fn perft_position_3() {
+    let fen = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    assert_eq!(perft(fen, 1), 14);
+    assert_eq!(perft(fen, 2), 191);
+    assert_eq!(perft(fen, 3), 2812);
+    assert_eq!(perft(fen, 4), 43238);
+    assert_eq!(perft(fen, 5), 674624);
+}
+
+#[test]
+fn perft_position_4() {
+    let fen = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    assert_eq!(perft(fen, 1), 6);
+    assert_eq!(perft(fen, 2), 264);
+    assert_eq!(perft(fen, 3), 9467);
+    assert_eq!(perft(fen, 4), 422333);
+}
+
+#[test]
+fn perft_position_4_mirrored() {
+    let fen = "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+    assert_eq!(perft(fen, 1), 6);
+    assert_eq!(perft(fen, 2), 264);
+    assert_eq!(perft(fen, 3), 9467);
+    assert_eq!(perft(fen, 4), 422333);
+}
+
+#[test]
+fn perft_position_5() {
+    let fen = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    assert_eq!(perft(fen, 1), 44);
+    assert_eq!(perft(fen, 2), 1486);
+    assert_eq!(perft(fen, 3), 62379);
+}
+
+#[test]
+fn perft_position_6() {
+    let fen = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+    assert_eq!(perft(fen, 1), 46);
+    assert_eq!(perft(fen, 2), 2079);
+    assert_eq!(perft(fen, 3), 89890);
+}
+
+#[test]
+#[ignore]
+fn perft_deep() {
+    let startpos = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    assert_eq!(perft(startpos, 5), 4865609);
+    assert_eq!(perft(kiwipete, 4), 4085603);
+    assert_eq!(
+        perft("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 6),
+        11030083
+    );
+    assert_eq!(
+        perft(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            5
+        ),
+        15833292
+    );
+    assert_eq!(
+        perft(
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            4
+        ),
+        2103487
+    );
+    assert_eq!(
+        perft(
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            4
+        ),
+        3894594
+    );
+}
+
+#[test]
+fn underpromotions_counted() {
+    let state = State::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let divide = state.divide(1);
+    let promotions: Vec<String> = divide
+        .iter()
+        .filter(|(mv, _)| mv.from == Position::parse("b7").unwrap())
+        .map(|(mv, _)| mv.to_string())
+        .collect();
+    assert_eq!(promotions, ["b7b8b", "b7b8n", "b7b8q", "b7b8r"]);
+}
+
+#[test]
+fn divide_sums_to_perft() {
+    let state =
+        State::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    let divide = state.divide(2);
+    assert_eq!(divide.len(), 48);
+    assert_eq!(divide.iter().map(|(_, n)| n).sum::<u64>(), state.perft(2));
+}
@@ -0,0 +1,169 @@
+use chess_core::*;
+
+fn play(game: &mut Game, moves: &[&str]) -> State {
+    let mut state = game.start.clone();
+    for node in game.moves.iter() {
+        state.apply(node.mv);
+    }
+    for san in moves {
+        let mv = state.parse_san(san).unwrap();
+        assert!(state.apply(mv));
+        game.moves.push(Node::new(mv));
+    }
+    state
+}
+
+#[test]
+fn writes_seven_tag_roster_and_result() {
+    let mut game = Game::new(State::new());
+    game.set_tag("White", "Player \"One\"");
+    game.set_tag("Annotator", "Tests");
+    let state = play(&mut game, &["f3", "e5", "g4", "Qh4#"]);
+    game.ended = state.ended().clone();
+
+    assert_eq!(
+        game.to_pgn().unwrap(),
+        "[Event \"?\"]\n\
+         [Site \"?\"]\n\
+         [Date \"????.??.??\"]\n\
+         [Round \"?\"]\n\
+         [White \"Player \\\"One\\\"\"]\n\
+         [Black \"?\"]\n\
+         [Result \"0-1\"]\n\
+         [Annotator \"Tests\"]\n\
+         \n\
+         1. f3 e5 2. g4 Qh4# 0-1\n"
+    );
+}
+
+#[test]
+fn writes_fen_for_custom_start() {
+    let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 3 40";
+    let mut game = Game::new(State::from_fen(fen).unwrap());
+    play(&mut game, &["Kd7", "e4"]);
+    game.ended = Some(EndState::FiftyMoveRule);
+
+    let pgn = game.to_pgn().unwrap();
+    assert!(pgn.contains(
+        "[Result \"1/2-1/2\"]\n[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 3 40\"]\n"
+    ));
+    assert!(pgn.ends_with("\n\n40... Kd7 41. e4 1/2-1/2\n"));
+    assert!(!Game::new(State::new()).to_pgn().unwrap().contains("FEN"));
+}
+
+#[test]
+fn wraps_long_movetext() {
+    let mut game = Game::new(State::new());
+    let moves = ["Nf3", "Nf6", "Ng1", "Ng8"];
+    for _ in 0..10 {
+        play(&mut game, &moves);
+    }
+    let pgn = game.to_pgn().unwrap();
+    let movetext = pgn.split("\n\n").nth(1).unwrap();
+    assert!(movetext.lines().count() > 1);
+    assert!(movetext.lines().all(|line| line.len() <= 79));
+    assert!(movetext.ends_with(" 20. Ng1 Ng8 *\n"));
+}
+
+#[test]
+fn rejects_illegal_moves() {
+    let mut game = Game::new(State::new());
+    let mv = State::new().parse_san("e4").unwrap();
+    game.moves.push(Node::new(mv));
+    game.moves.push(Node::new(mv));
+    assert!(game.to_pgn().is_none());
+}
+
+const ANNOTATED: &str = r#"[Event "Casual \"blitz\""]
+[Site "?"]
+[Result "1-0"]
+
+{Opening comment} 1. e4 e5 2. Nf3 $1 Nc6 {Main line} (2... d6 3. d4 (3. Bc4) 3...
+exd4?! ; rest of line
+) 3. Bb5!? a6 1-0
+"#;
+
+#[test]
+fn reads_tags_comments_nags_and_variations() {
+    let game = Game::from_pgn(ANNOTATED).unwrap();
+    assert_eq!(game.tag("Event"), Some("Casual \"blitz\""));
+    assert_eq!(game.result(), "1-0");
+    assert_eq!(game.moves.len(), 6);
+    assert_eq!(
+        game.moves[0].comment_before.as_deref(),
+        Some("Opening comment")
+    );
+    assert_eq!(game.moves[2].nags, vec![1]);
+    assert_eq!(game.moves[4].nags, vec![5]);
+
+    let black = &game.moves[3];
+    assert_eq!(black.comment.as_deref(), Some("Main line"));
+    assert_eq!(black.variations.len(), 1);
+    let variation = &black.variations[0];
+    assert_eq!(variation.len(), 3);
+    assert_eq!(variation[0].mv.to_string(), "d7d6");
+    assert_eq!(variation[1].variations[0][0].mv.to_string(), "f1c4");
+    assert_eq!(variation[2].nags, vec![6]);
+    assert_eq!(variation[2].comment.as_deref(), Some("rest of line"));
+}
+
+#[test]
+fn round_trips_annotated_games() {
+    let pgn = Game::from_pgn(ANNOTATED).unwrap().to_pgn().unwrap();
+    assert!(pgn.ends_with(
+        "{Opening comment} 1. e4 e5 2. Nf3 $1 Nc6 {Main line} (2... d6 3. d4 (3. Bc4)\n\
+         3... exd4 $6 {rest of line}) 3. Bb5 $5 a6 1-0\n"
+    ));
+    assert_eq!(Game::from_pgn(&pgn).unwrap().to_pgn().unwrap(), pgn);
+}
+
+#[test]
+fn reads_multiple_games() {
+    let pgn = "[Event \"One\"]\n\n1. e4 *\n\n[Event \"Two\"]\n[FEN \"4k3/8/8/8/8/8/8/4K2R w K - 0 1\"]\n\n1. O-O Kd7 1/2-1/2\n\n1. d4\n";
+    let games = Game::parse_all(pgn).unwrap();
+    assert_eq!(games.len(), 3);
+    assert_eq!(games[0].tag("Event"), Some("One"));
+    assert_eq!(games[1].start.to_fen(), "4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+    assert_eq!(games[1].moves.len(), 2);
+    assert_eq!(games[1].result(), "1/2-1/2");
+    assert_eq!(games[2].result(), "*");
+    assert_eq!(games[2].moves[0].mv.to_string(), "d2d4");
+}
+
+#[test]
+fn detects_checkmate_when_reading() {
+    let game = Game::from_pgn("1. f3 e5 2. g4 Qh4#").unwrap();
+    assert_eq!(game.result(), "0-1");
+    assert!(game.end_state().unwrap().ended().is_some());
+}
+
+#[test]
+fn errors_give_line_and_column() {
+    let error = |pgn: &str| {
+        let e = Game::from_pgn(pgn).err().unwrap();
+        (e.line, e.column, e.kind)
+    };
+    assert_eq!(
+        error("[Event \"?\"]\n\n1. e4 e5 2. Ke3"),
+        (
+            3,
+            13,
+            PgnErrorKind::InvalidMove("Ke3".to_string(), SanError::Illegal)
+        )
+    );
+    assert_eq!(
+        error("1. e4 {never closed"),
+        (1, 7, PgnErrorKind::UnterminatedComment)
+    );
+    assert_eq!(
+        error("1. e4 (1. d4"),
+        (1, 13, PgnErrorKind::UnterminatedVariation)
+    );
+    assert_eq!(error("1. e4 )"), (1, 7, PgnErrorKind::Unexpected(')')));
+    assert_eq!(error("[Event ?]"), (1, 1, PgnErrorKind::InvalidTag));
+    assert_eq!(error("\n  \n"), (3, 1, PgnErrorKind::NoGame));
+    assert!(matches!(
+        error("[FEN \"8/8/8/8/8/8/8/8 w - - 0 1\"]\n1. e4").2,
+        PgnErrorKind::InvalidFen(_)
+    ));
+}
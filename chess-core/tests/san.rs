@@ -0,0 +1,78 @@
+use chess_core::*;
+
+fn san(fen: &str, from: &str, to: &str, promotion: Option<Variant>) -> String {
+    let state = State::from_fen(fen).unwrap();
+    let mv = state
+        .find_move(
+            Position::parse(from).unwrap(),
+            Position::parse(to).unwrap(),
+            promotion,
+        )
+        .unwrap();
+    state.to_san(mv).unwrap()
+}
+
+fn parse(fen: &str, san: &str) -> Result<String, SanError> {
+    let state = State::from_fen(fen).unwrap();
+    state.parse_san(san).map(|mv| mv.to_string())
+}
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+#[test]
+fn writes_basic_moves() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(san(start, "g1", "f3", None), "Nf3");
+    assert_eq!(san(start, "e2", "e4", None), "e4");
+    assert_eq!(san(KIWIPETE, "e1", "g1", None), "O-O");
+    assert_eq!(san(KIWIPETE, "e1", "c1", None), "O-O-O");
+    assert_eq!(san(KIWIPETE, "e5", "f7", None), "Nxf7");
+    assert_eq!(san(KIWIPETE, "e2", "a6", None), "Bxa6");
+    assert_eq!(san(KIWIPETE, "d5", "e6", None), "dxe6");
+}
+
+#[test]
+fn writes_disambiguation() {
+    let rooks = "4k3/8/8/8/8/8/4K3/R6R w - - 0 1";
+    assert_eq!(san(rooks, "a1", "d1", None), "Rad1");
+    assert_eq!(san(rooks, "a1", "a8", None), "Ra8+");
+    let queens = "6k1/8/8/1Q6/8/8/1Q5Q/4K3 w - - 0 1";
+    assert_eq!(san(queens, "b2", "e5", None), "Qb2e5");
+    assert_eq!(
+        san("k7/3Q4/1Q6/8/8/8/8/4K3 w - - 0 1", "b6", "b7", None),
+        "Qbb7#"
+    );
+}
+
+#[test]
+fn writes_promotions_and_en_passant() {
+    let fen = "1n5k/P7/8/8/8/8/8/4K3 w - - 0 1";
+    assert_eq!(san(fen, "a7", "b8", Some(Variant::Queen)), "axb8=Q+");
+    assert_eq!(san(fen, "a7", "a8", Some(Variant::Knight)), "a8=N");
+    let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+    assert_eq!(san(fen, "e5", "d6", None), "exd6");
+}
+
+#[test]
+fn parses_moves() {
+    assert_eq!(parse(KIWIPETE, "O-O-O").unwrap(), "e1c1");
+    assert_eq!(parse(KIWIPETE, "0-0").unwrap(), "e1g1");
+    assert_eq!(parse(KIWIPETE, "Nxf7").unwrap(), "e5f7");
+    assert_eq!(parse(KIWIPETE, "gxh3+").unwrap(), "g2h3");
+    let rooks = "4k3/8/8/8/8/8/4K3/R6R w - - 0 1";
+    assert_eq!(parse(rooks, "Rd1"), Err(SanError::Ambiguous));
+    assert_eq!(parse(rooks, "Rhd1").unwrap(), "h1d1");
+    let fen = "1n5k/P7/8/8/8/8/8/4K3 w - - 0 1";
+    assert_eq!(parse(fen, "a8=R").unwrap(), "a7a8r");
+    assert_eq!(parse(fen, "axb8Q").unwrap(), "a7b8q");
+    assert_eq!(parse(fen, "a8"), Err(SanError::Illegal));
+    assert_eq!(parse(fen, "zz"), Err(SanError::Invalid));
+}
+
+#[test]
+fn round_trips_every_legal_move() {
+    let state = State::from_fen(KIWIPETE).unwrap();
+    for mv in state.legal_moves() {
+        assert_eq!(state.parse_san(&state.to_san(mv).unwrap()), Ok(mv));
+    }
+}
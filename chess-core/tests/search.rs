@@ -0,0 +1,120 @@
+use chess_core::*;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn search(fen: &str, depth: u32) -> SearchResult {
+    Searcher::new().search(
+        &State::from_fen(fen).unwrap(),
+        Limits {
+            depth: Some(depth),
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn finds_mate_in_one() {
+    let result = search(
+        "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+        4,
+    );
+    assert_eq!(result.best_move.unwrap().to_string(), "h5f7");
+    assert_eq!(result.score, Score::Mate(1));
+}
+
+#[test]
+fn finds_mate_in_two() {
+    // 1. Nf6+ gxf6 2. Bxf7#
+    let result = search(
+        "r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 1",
+        4,
+    );
+    assert_eq!(result.best_move.unwrap().to_string(), "d5f6");
+    assert_eq!(result.score, Score::Mate(2));
+}
+
+#[test]
+fn wins_material() {
+    let result = search("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", 3);
+    assert_eq!(result.best_move.unwrap().to_string(), "d2d5");
+    assert!(matches!(result.score, Score::Centipawns(score) if score > 300));
+}
+
+#[test]
+fn principal_variation_is_legal() {
+    let result = search(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        4,
+    );
+    assert_eq!(result.depth, 4);
+    assert_eq!(result.best_move, result.pv.first().copied());
+    let mut state =
+        State::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    for &mv in result.pv.iter() {
+        assert!(state.apply(mv));
+    }
+}
+
+#[test]
+fn no_move_when_the_game_is_over() {
+    let result = search("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1", 3);
+    assert_eq!(result.best_move, None);
+}
+
+#[test]
+fn reports_each_iteration() {
+    let mut depths = Vec::new();
+    Searcher::new().search_with(
+        &State::new(),
+        Limits {
+            depth: Some(3),
+            ..Default::default()
+        },
+        |result| depths.push(result.depth),
+    );
+    assert_eq!(depths, vec![1, 2, 3]);
+}
+
+#[test]
+fn respects_node_and_time_limits() {
+    let state = State::new();
+    let result = Searcher::new().search(
+        &state,
+        Limits {
+            nodes: Some(5000),
+            ..Default::default()
+        },
+    );
+    assert!(result.best_move.is_some());
+    assert!(result.nodes < 10000);
+
+    let start = Instant::now();
+    let result = Searcher::new().search(
+        &state,
+        Limits {
+            time: Some(Duration::from_millis(100)),
+            ..Default::default()
+        },
+    );
+    assert!(result.best_move.is_some());
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn stops_when_asked() {
+    let mut searcher = Searcher::new();
+    let stop = searcher.stop_handle();
+    let handle = thread::spawn(move || searcher.search(&State::new(), Limits::default()));
+    thread::sleep(Duration::from_millis(100));
+    stop.store(true, std::sync::atomic::Ordering::Relaxed);
+    assert!(handle.join().unwrap().best_move.is_some());
+}
+
+#[test]
+fn score_display() {
+    assert_eq!(Score::Centipawns(35).to_string(), "+0.35");
+    assert_eq!(Score::Centipawns(-120).to_string(), "-1.20");
+    assert_eq!(Score::Mate(3).to_string(), "#3");
+    assert_eq!(Score::Mate(-2).to_string(), "#-2");
+}
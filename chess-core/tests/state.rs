@@ -0,0 +1,166 @@
+use chess_core::*;
+
+#[test]
+fn en_passant_discovered_check() {
+    // Capturing en passant would expose the king along the rank.
+    let state = State::from_fen("8/8/8/KPp4r/8/8/8/7k w - c6 0 1").unwrap();
+    assert!(!state.is_valid_move(
+        Position::parse("b5").unwrap(),
+        Position::parse("c6").unwrap()
+    ));
+}
+
+#[test]
+fn no_castling_through_check() {
+    let state = State::from_fen("4k3/8/8/8/8/8/5r2/R3K2R w KQ - 0 1").unwrap();
+    let king = Position::parse("e1").unwrap();
+    assert!(!state.is_valid_move(king, Position::parse("g1").unwrap()));
+    assert!(state.is_valid_move(king, Position::parse("c1").unwrap()));
+}
+
+#[test]
+fn unmake_restores_position() {
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+    ]
+    .iter()
+    {
+        let mut state = State::from_fen(fen).unwrap();
+        let moves = state.legal_moves();
+        for &mv in moves.iter() {
+            let undo = state.make_move(mv).unwrap();
+            let mut expected = State::from_fen(fen).unwrap();
+            assert!(expected.apply(mv));
+            assert_eq!(state.to_fen(), expected.to_fen());
+            state.unmake_move(undo);
+            assert_eq!(state.to_fen(), *fen);
+            assert_eq!(state.legal_moves().len(), moves.len());
+        }
+    }
+}
+
+#[test]
+fn hash_matches_transpositions() {
+    let play = |moves: &[&str]| {
+        let mut state = State::new();
+        for san in moves {
+            let mv = state.parse_san(san).unwrap();
+            assert!(state.apply(mv));
+        }
+        state
+    };
+    let a = play(&["Nf3", "Nf6", "Nc3", "Nc6"]);
+    let b = play(&["Nc3", "Nc6", "Nf3", "Nf6"]);
+    assert_eq!(a.hash(), b.hash());
+    assert_eq!(a.hash(), State::from_fen(&a.to_fen()).unwrap().hash());
+    assert_ne!(a.hash(), play(&["Nf3", "Nf6", "Nc3"]).hash());
+}
+
+#[test]
+fn hash_changes_while_promoting() {
+    let mut state = State::from_fen("8/P7/8/8/8/8/k7/4K3 w - - 0 1").unwrap();
+    let before = state.hash();
+    let (from, to) = (
+        Position::parse("a7").unwrap(),
+        Position::parse("a8").unwrap(),
+    );
+    assert!(state.try_move(from, to));
+    assert!(state.promoting());
+    assert_ne!(state.hash(), before);
+    assert!(state.promote(Variant::Queen));
+    assert_eq!(
+        state.hash(),
+        State::from_fen(&state.to_fen()).unwrap().hash()
+    );
+}
+
+#[test]
+fn hash_ignores_uncapturable_en_passant() {
+    let hash = |fen| State::from_fen(fen).unwrap().hash();
+    assert_eq!(
+        hash("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+        hash("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+    );
+    assert_ne!(
+        hash("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+        hash("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+    );
+}
+
+#[test]
+fn hash_restored_by_unmake() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let mut state = State::from_fen(fen).unwrap();
+    let hash = state.hash();
+    for mv in state.legal_moves() {
+        let undo = state.make_move(mv).unwrap();
+        assert_eq!(
+            state.hash(),
+            State::from_fen(&state.to_fen()).unwrap().hash()
+        );
+        state.unmake_move(undo);
+        assert_eq!(state.hash(), hash);
+    }
+}
+
+#[test]
+fn fifty_move_rule_can_be_claimed() {
+    let mut state = State::from_fen("4k3/8/8/8/8/8/R7/4K3 w - - 98 60").unwrap();
+    let rook = state.parse_san("Ra3").unwrap();
+    assert!(state.apply(rook));
+    assert!(!state.can_claim_fifty_moves());
+    let king = state.parse_san("Kd7").unwrap();
+    assert!(state.apply(king));
+    assert_eq!(state.fullmove_number(), 61);
+    assert!(state.can_claim_fifty_moves());
+    assert!(state.claim_fifty_moves());
+    assert!(matches!(state.ended(), Some(EndState::FiftyMoveRule)));
+    assert!(state.legal_moves().is_empty());
+}
+
+#[test]
+fn seventy_five_move_rule_ends_game() {
+    let mut state = State::from_fen("4k3/8/8/8/8/8/R7/4K3 w - - 149 100").unwrap();
+    let mv = state.parse_san("Ra3").unwrap();
+    assert!(state.apply(mv));
+    assert!(matches!(state.ended(), Some(EndState::SeventyFiveMoveRule)));
+}
+
+#[test]
+fn flag_fall_against_insufficient_material_is_a_draw() {
+    let flag = |fen: &str, colour: Colour| {
+        let mut state = State::from_fen(fen).unwrap();
+        state.flag_fall(colour);
+        assert!(state.legal_moves().is_empty());
+        state.ended().clone().unwrap()
+    };
+    let rook = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1";
+    assert_eq!(flag(rook, Colour::Black), EndState::Timeout(Colour::White));
+    assert_eq!(
+        flag(rook, Colour::White),
+        EndState::TimeoutVsInsufficientMaterial
+    );
+    let knight = "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1";
+    assert_eq!(
+        flag(knight, Colour::Black),
+        EndState::TimeoutVsInsufficientMaterial
+    );
+    let blockers = "4k3/4p3/8/8/8/8/8/1N2K3 w - - 0 1";
+    assert_eq!(
+        flag(blockers, Colour::Black),
+        EndState::Timeout(Colour::White)
+    );
+}
+
+#[test]
+fn castling_rights_and_en_passant() {
+    let state = State::from_fen("r3k3/8/8/8/4Pp2/8/8/R3K2R b KQq e3 0 1").unwrap();
+    assert!(state.can_castle(Colour::White, true));
+    assert!(state.can_castle(Colour::White, false));
+    assert!(!state.can_castle(Colour::Black, true));
+    assert!(state.can_castle(Colour::Black, false));
+    assert_eq!(state.en_passant(), Position::parse("e3"));
+    assert_eq!(State::new().en_passant(), None);
+}
@@ -0,0 +1,243 @@
+use chess_core::*;
+use std::time::Duration;
+
+#[test]
+fn parse_uci() {
+    let state = State::new();
+    assert_eq!(state.parse_uci("e2e4").unwrap().to_string(), "e2e4");
+    assert_eq!(state.parse_uci("e2e5"), None);
+    assert_eq!(state.parse_uci("e2"), None);
+
+    let state = State::from_fen("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let mv = state.parse_uci("e7e8n").unwrap();
+    assert_eq!(mv.promotion, Some(Variant::Knight));
+    assert_eq!(state.parse_uci("e7e8"), None);
+    assert_eq!(state.parse_uci("e7e8k"), None);
+}
+
+#[test]
+fn go_command() {
+    assert_eq!(Go::default().to_string(), "go infinite");
+    let go = Go {
+        clock: Some([Duration::from_secs(60), Duration::from_millis(59500)]),
+        increment: Some([Duration::from_secs(1); 2]),
+        moves_to_go: Some(20),
+        ..Default::default()
+    };
+    assert_eq!(
+        go.to_string(),
+        "go wtime 60000 btime 59500 winc 1000 binc 1000 movestogo 20"
+    );
+    let limits = Limits {
+        depth: Some(6),
+        time: Some(Duration::from_millis(250)),
+        ..Default::default()
+    };
+    assert_eq!(Go::from(limits).to_string(), "go depth 6 movetime 250");
+}
+
+#[test]
+fn engine_can_be_shared() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Engine>();
+}
+
+#[cfg(unix)]
+mod scripted {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    // A stand-in engine that introduces itself, echoes the commands it is
+    // sent as info strings and gives a fixed search result.
+    const SCRIPT: &str = r#"
+while read -r line; do
+    case "$line" in
+        uci)
+            echo "id name Scripted Engine"
+            echo "id author Tests"
+            echo "option name Hash type spin default 16 min 1 max 1024"
+            echo "option name Ponder type check default false"
+            echo "option name Play Style type combo default Normal var Solid var Normal var Very Risky"
+            echo "option name Clear Hash type button"
+            echo "option name Book File type string default <empty>"
+            echo "uciok"
+            ;;
+        isready) echo "readyok" ;;
+        go*)
+            echo "info depth 1 seldepth 2 multipv 1 score cp 35 nodes 20 nps 20000 time 1 pv e2e4 e7e5 e1e8"
+            echo "info depth 2 score mate -3 upperbound nodes 400 time 3 pv e2e4"
+            echo "bestmove e2e4 ponder e7e5"
+            ;;
+        quit) exit 0 ;;
+        *) echo "info string $line" ;;
+    esac
+done
+"#;
+
+    fn start(name: &str) -> Engine {
+        let path: PathBuf =
+            std::env::temp_dir().join(format!("chess-core-{}-{}.sh", std::process::id(), name));
+        fs::write(&path, SCRIPT).unwrap();
+        let mut command = Command::new("sh");
+        command.arg(&path);
+        let engine = Engine::with_command(command).unwrap();
+        fs::remove_file(&path).unwrap();
+        engine
+    }
+
+    fn echo(engine: &mut Engine) -> String {
+        match engine.wait(Duration::from_secs(5)).unwrap() {
+            EngineEvent::Info(Info {
+                string: Some(string),
+                ..
+            }) => string,
+            event => panic!("unexpected {:?}", event),
+        }
+    }
+
+    #[test]
+    fn handshake() {
+        let engine = start("handshake");
+        assert_eq!(engine.name(), Some("Scripted Engine"));
+        assert_eq!(engine.author(), Some("Tests"));
+        let options = engine.options();
+        assert_eq!(options.len(), 5);
+        assert_eq!(
+            options[0],
+            EngineOption {
+                name: "Hash".to_string(),
+                kind: OptionKind::Spin {
+                    default: 16,
+                    min: 1,
+                    max: 1024,
+                },
+            }
+        );
+        assert_eq!(options[1].kind, OptionKind::Check { default: false });
+        assert_eq!(
+            options[2],
+            EngineOption {
+                name: "Play Style".to_string(),
+                kind: OptionKind::Combo {
+                    default: "Normal".to_string(),
+                    choices: vec![
+                        "Solid".to_string(),
+                        "Normal".to_string(),
+                        "Very Risky".to_string()
+                    ],
+                },
+            }
+        );
+        assert_eq!(options[3].kind, OptionKind::Button);
+        assert_eq!(
+            options[4].kind,
+            OptionKind::String {
+                default: String::new()
+            }
+        );
+    }
+
+    #[test]
+    fn sends_options_and_positions() {
+        let mut engine = start("commands");
+        engine.set_option("hash", "64").unwrap();
+        assert_eq!(echo(&mut engine), "setoption name Hash value 64");
+        engine.set_option("Clear Hash", "").unwrap();
+        assert_eq!(echo(&mut engine), "setoption name Clear Hash");
+        assert!(matches!(
+            engine.set_option("Threads", "2"),
+            Err(UciError::UnknownOption(_))
+        ));
+
+        let start = State::new();
+        engine.set_position(&start, &[]).unwrap();
+        assert_eq!(echo(&mut engine), "position startpos");
+        let mut moves = Vec::new();
+        let mut state = start.clone();
+        for san in ["e4", "c5", "Nf3"].iter() {
+            let mv = state.parse_san(san).unwrap();
+            state.apply(mv);
+            moves.push(mv);
+        }
+        engine.set_position(&start, &moves).unwrap();
+        assert_eq!(echo(&mut engine), "position startpos moves e2e4 c7c5 g1f3");
+
+        let fen = "4k3/8/8/8/8/8/8/4K2R w K - 0 1";
+        let state = State::from_fen(fen).unwrap();
+        let castle = state.parse_uci("e1g1").unwrap();
+        engine.set_position(&state, &[castle]).unwrap();
+        assert_eq!(
+            echo(&mut engine),
+            format!("position fen {} moves e1g1", fen)
+        );
+        assert!(matches!(
+            engine.set_position(&state, &[castle, castle]),
+            Err(UciError::IllegalMove(_))
+        ));
+    }
+
+    #[test]
+    fn search() {
+        let mut engine = start("search");
+        engine.new_game().unwrap();
+        engine.set_position(&State::new(), &[]).unwrap();
+        assert_eq!(echo(&mut engine), "position startpos");
+        engine.go(&Go::default()).unwrap();
+
+        let info = match engine.wait(Duration::from_secs(5)).unwrap() {
+            EngineEvent::Info(info) => info,
+            event => panic!("unexpected {:?}", event),
+        };
+        assert_eq!(info.depth, Some(1));
+        assert_eq!(info.selective_depth, Some(2));
+        assert_eq!(info.multi_pv, Some(1));
+        assert_eq!(info.score, Some(Score::Centipawns(35)));
+        assert_eq!(info.nodes, Some(20));
+        assert_eq!(info.time, Some(Duration::from_millis(1)));
+        // The last move is illegal and left out.
+        let pv: Vec<String> = info.pv.iter().map(|mv| mv.to_string()).collect();
+        assert_eq!(pv, ["e2e4", "e7e5"]);
+
+        match engine.wait(Duration::from_secs(5)).unwrap() {
+            EngineEvent::Info(info) => assert_eq!(info.score, Some(Score::Mate(-3))),
+            event => panic!("unexpected {:?}", event),
+        }
+        match engine.wait(Duration::from_secs(5)).unwrap() {
+            EngineEvent::BestMove { best, ponder } => {
+                assert_eq!(best.unwrap().to_string(), "e2e4");
+                assert_eq!(ponder.unwrap().to_string(), "e7e5");
+            }
+            event => panic!("unexpected {:?}", event),
+        }
+        assert!(engine.poll().unwrap().is_none());
+    }
+
+    #[test]
+    fn reports_exit() {
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg("read line; echo uciok; read line; echo readyok");
+        let mut engine = Engine::with_command(command).unwrap();
+        assert!(matches!(
+            engine.wait(Duration::from_secs(5)),
+            Err(UciError::Exited)
+        ));
+    }
+
+    #[test]
+    fn fails_to_start() {
+        assert!(matches!(
+            Engine::start("/nonexistent/engine"),
+            Err(UciError::Io(_))
+        ));
+        let mut command = Command::new("sh");
+        command.arg("-c").arg("read line");
+        assert!(matches!(
+            Engine::with_command(command),
+            Err(UciError::Exited)
+        ));
+    }
+}
@@ -0,0 +1,232 @@
+use chess_core::*;
+
+fn play(state: &mut State, moves: &[&str]) {
+    for uci in moves {
+        let mv = state.parse_uci(uci).unwrap();
+        assert!(state.apply(mv), "{}", uci);
+    }
+}
+
+#[test]
+fn rules_lookup() {
+    assert_eq!(RULES[0].name(), "Standard");
+    assert_eq!(
+        find_rules("king of the hill").unwrap().name(),
+        "King of the Hill"
+    );
+    assert_eq!(find_rules("Chess960").unwrap().name(), "Standard");
+    assert_eq!(find_rules("crazyhouse").unwrap().name(), "Crazyhouse");
+    assert!(find_rules("Bughouse").is_none());
+    for rules in RULES.iter() {
+        let state = State::with_rules(*rules);
+        assert_eq!(state.to_fen(), rules.start_fen());
+        assert_eq!(state.rules().name(), rules.name());
+    }
+    assert!(State::with_rules(&Standard) == State::new());
+    assert!(State::with_rules(&Atomic) != State::new());
+}
+
+#[test]
+fn king_of_the_hill() {
+    let start = State::with_rules(&KingOfTheHill);
+    assert_eq!(start.perft(4), 197281);
+
+    let mut state =
+        State::from_fen_with_rules("4k3/8/8/8/8/4K3/8/8 w - - 0 1", &KingOfTheHill).unwrap();
+    assert!(state.ended().is_none());
+    play(&mut state, &["e3e4"]);
+    assert_eq!(state.ended(), &Some(EndState::VariantWin(Colour::White)));
+}
+
+#[test]
+fn three_check() {
+    let mut state = State::with_rules(&ThreeCheck);
+    assert_eq!(state.perft(3), 8902);
+    play(
+        &mut state,
+        &[
+            "e2e4", "e7e5", "f1c4", "g8f6", "c4f7", "e8f7", "d1h5", "f7e7",
+        ],
+    );
+    assert_eq!(state.checks_given(Colour::White), 2);
+    assert_eq!(
+        state.to_fen(),
+        "rnbq1b1r/ppppk1pp/5n2/4p2Q/4P3/8/PPPP1PPP/RNB1K1NR w KQ - 2 5 +2+0"
+    );
+    assert!(state.ended().is_none());
+    play(&mut state, &["h5e5"]);
+    assert_eq!(state.ended(), &Some(EndState::VariantWin(Colour::White)));
+
+    let state =
+        State::from_fen_with_rules("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +2+1", &ThreeCheck).unwrap();
+    assert_eq!(state.checks_given(Colour::Black), 1);
+    assert_eq!(state.ended(), &Some(EndState::InsufficientMaterial));
+    let error = State::from_fen_with_rules("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +x", &ThreeCheck);
+    assert_eq!(error.err().unwrap().field, FenField::Checks);
+}
+
+#[test]
+fn atomic() {
+    assert_eq!(State::with_rules(&Atomic).perft(4), 197326);
+
+    // Capturing blows up every piece other than a pawn around the capture.
+    let mut state =
+        State::from_fen_with_rules("4k3/8/2bnp3/3r4/8/8/8/3QK3 w - - 0 1", &Atomic).unwrap();
+    play(&mut state, &["d1d5"]);
+    assert_eq!(state.to_fen(), "4k3/8/4p3/8/8/8/8/4K3 b - - 0 1");
+
+    // Blowing up the king wins, and a king may not capture.
+    let mut state =
+        State::from_fen_with_rules("4k3/3p4/8/8/8/8/8/3QK3 w - - 0 1", &Atomic).unwrap();
+    play(&mut state, &["d1d7"]);
+    assert_eq!(state.ended(), &Some(EndState::VariantWin(Colour::White)));
+    let state = State::from_fen_with_rules("4k3/8/8/8/8/8/3p4/4K3 w - - 0 1", &Atomic).unwrap();
+    assert!(state.parse_uci("e1d2").is_none());
+
+    // Kings next to each other cannot be checked.
+    let state = State::from_fen_with_rules("8/8/8/8/8/4k3/4K3/4R2r w - - 0 1", &Atomic).unwrap();
+    assert!(state.king_in_check().is_none());
+
+    // A pawn that promotes by capturing is blown up along with its promotion.
+    let mut state =
+        State::from_fen_with_rules("1n2k2r/P7/8/8/8/8/8/4K2R w - - 0 1", &Atomic).unwrap();
+    let mv = state.parse_uci("a7b8n").unwrap();
+    assert_eq!(mv.promotion, Some(Variant::Knight));
+    assert!(state.apply(mv));
+    assert!(!state.promoting());
+    assert_eq!(state.get_piece(mv.to), None);
+    assert_eq!(state.to_fen(), "4k2r/8/8/8/8/8/8/4K2R b - - 0 1");
+}
+
+#[test]
+fn antichess() {
+    assert_eq!(State::with_rules(&Antichess).perft(4), 153299);
+
+    // Captures are forced, and the king can be taken like any other piece.
+    let mut state = State::with_rules(&Antichess);
+    play(&mut state, &["e2e4", "d7d5"]);
+    assert_eq!(state.legal_moves().len(), 1);
+    let state = State::from_fen_with_rules("8/1P6/8/8/8/8/8/k1K5 w - - 0 1", &Antichess).unwrap();
+    assert!(state.parse_uci("b7b8k").is_some());
+    assert!(state.parse_uci("c1b1").is_some());
+
+    let mut state = State::from_fen_with_rules("8/8/8/8/8/8/1p6/R7 w - - 0 1", &Antichess).unwrap();
+    play(&mut state, &["a1a2", "b2b1q"]);
+    assert!(state.ended().is_none());
+    play(&mut state, &["a2a1", "b1a1"]);
+    assert_eq!(state.ended(), &Some(EndState::VariantWin(Colour::White)));
+
+    // With more than one king, king moves are told apart in SAN.
+    let state = State::from_fen_with_rules("8/8/8/8/8/2K1K3/8/7k w - - 0 1", &Antichess).unwrap();
+    for (uci, san) in [("c3d4", "Kcd4"), ("e3d4", "Ked4"), ("c3c4", "Kc4")].iter() {
+        let mv = state.parse_uci(uci).unwrap();
+        assert_eq!(state.to_san(mv).unwrap(), *san);
+        assert_eq!(state.parse_san(san), Ok(mv));
+    }
+}
+
+#[test]
+fn horde() {
+    let state = State::with_rules(&Horde);
+    assert_eq!(state.perft(1), 8);
+    assert_eq!(state.perft(2), 128);
+    assert_eq!(state.perft(3), 1274);
+    assert_eq!(state.perft(4), 23310);
+
+    // White loses once every pawn is gone.
+    let mut state = State::from_fen_with_rules("4k3/8/8/8/8/8/8/3P4 b - - 0 1", &Horde).unwrap();
+    assert!(state.parse_uci("d1d3").is_none());
+    play(&mut state, &["e8d7"]);
+    play(&mut state, &["d1d3"]);
+    let mut state = State::from_fen_with_rules("4k3/8/8/8/8/8/3r4/3P4 b - - 0 1", &Horde).unwrap();
+    play(&mut state, &["d2d1"]);
+    assert_eq!(state.ended(), &Some(EndState::VariantWin(Colour::Black)));
+}
+
+#[test]
+fn racing_kings() {
+    let state = State::with_rules(&RacingKings);
+    assert_eq!(state.perft(1), 21);
+    assert_eq!(state.perft(2), 421);
+    assert_eq!(state.perft(3), 11264);
+    assert_eq!(state.perft(4), 296242);
+
+    // Black gets one more move to draw after White's king arrives.
+    let mut state =
+        State::from_fen_with_rules("8/1k3K2/8/8/8/8/8/8 w - - 0 1", &RacingKings).unwrap();
+    play(&mut state, &["f7f8"]);
+    assert!(state.ended().is_none());
+    let mut state =
+        State::from_fen_with_rules("8/5K2/1k6/8/8/8/8/8 w - - 0 1", &RacingKings).unwrap();
+    play(&mut state, &["f7f8"]);
+    assert_eq!(state.ended(), &Some(EndState::VariantWin(Colour::White)));
+    let mut state =
+        State::from_fen_with_rules("8/5K2/1k6/8/8/8/8/8 w - - 0 1", &RacingKings).unwrap();
+    play(&mut state, &["f7e7", "b6b7", "e7e8", "b7b8"]);
+    assert_eq!(state.ended(), &Some(EndState::VariantDraw));
+
+    // Giving check is not allowed.
+    let state = State::from_fen_with_rules("8/8/8/8/8/8/k7/6RK w - - 0 1", &RacingKings).unwrap();
+    assert!(state.parse_uci("g1g2").is_none());
+    assert!(state.parse_uci("g1a1").is_none());
+}
+
+#[test]
+fn pgn_variant_tag() {
+    let start = State::with_rules(&KingOfTheHill);
+    let mut game = Game::new(start.clone());
+    let mut state = start;
+    for uci in ["e2e3", "a7a6", "e1e2", "a6a5", "e2d3", "a5a4", "d3d4"].iter() {
+        let mv = state.parse_uci(uci).unwrap();
+        state.apply(mv);
+        game.moves.push(Node::new(mv));
+    }
+    game.ended = state.ended().clone();
+    let pgn = game.to_pgn().unwrap();
+    assert!(pgn.contains("[Variant \"King of the Hill\"]"));
+    assert!(!pgn.contains("[FEN"));
+    assert!(pgn.ends_with("4. Kd4 1-0\n"));
+    let read = Game::from_pgn(&pgn).unwrap();
+    assert!(read.end_state().unwrap() == state);
+
+    let error = Game::from_pgn("[Variant \"Bughouse\"]\n\n1. e4 *")
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.kind,
+        PgnErrorKind::UnknownVariant("Bughouse".to_string())
+    );
+}
+
+#[test]
+fn pgn_atomic_promotion_capture() {
+    let start = State::from_fen_with_rules("1n2k2r/P7/8/8/8/8/8/4K2R w - - 0 1", &Atomic).unwrap();
+    let mut game = Game::new(start.clone());
+    let mut state = start;
+    for uci in ["a7b8q", "e8d7", "e1e2"].iter() {
+        let mv = state.parse_uci(uci).unwrap();
+        assert!(state.apply(mv), "{}", uci);
+        game.moves.push(Node::new(mv));
+    }
+    let pgn = game.to_pgn().unwrap();
+    assert!(pgn.contains("1. axb8=Q Kd7 2. Ke2 *"), "{}", pgn);
+    let read = Game::from_pgn(&pgn).unwrap();
+    assert_eq!(read.moves.len(), 3);
+    assert!(read.end_state().unwrap() == state);
+}
+
+#[test]
+fn search_plays_for_variant_goals() {
+    let state =
+        State::from_fen_with_rules("4k3/8/8/8/8/4K3/8/8 w - - 0 1", &KingOfTheHill).unwrap();
+    let result = Searcher::new().search(
+        &state,
+        Limits {
+            depth: Some(2),
+            ..Default::default()
+        },
+    );
+    assert_eq!(result.score, Score::Mate(1));
+    let to = result.pv[0].to.to_string();
+    assert!(to == "d4" || to == "e4", "{}", to);
+}
@@ -3,6 +3,12 @@ use super::*;
 pub struct Textures(pub HashMap<Piece, Handle<ColorMaterial>>);
 pub struct PieceEntities(pub HashMap<Position, Entity>);
 pub struct PieceEntity;
+pub struct RankLabel;
+// A file or rank label, counting from the bottom left of the screen.
+pub struct Coordinate {
+    file: bool,
+    index: i8,
+}
 pub enum Drag {
     None,
     Mouse(Vec2),
@@ -129,61 +135,77 @@ pub fn setup(
         font_size: 20.0,
         color: Color::rgb(0.95, 0.95, 0.95),
     };
-    for (i, c) in ('a'..='h').enumerate() {
-        text(
-            &mut commands,
-            style.clone(),
-            &c.to_string(),
-            Vec2::new(
-                (i as f32 - 3.5) * SQUARE_SIZE + CENTRE_X,
-                CENTRE_Y + SQUARE_SIZE * 4.0 + 13.0,
-            ),
-        );
-        text(
-            &mut commands,
-            style.clone(),
-            &c.to_string(),
-            Vec2::new(
-                (i as f32 - 3.5) * SQUARE_SIZE + CENTRE_X,
-                CENTRE_Y - SQUARE_SIZE * 4.0 - 10.0,
-            ),
-        );
-    }
     for i in 0..8 {
-        text(
-            &mut commands,
-            style.clone(),
-            &(i + 1).to_string(),
-            Vec2::new(
-                CENTRE_X + SQUARE_SIZE * 4.0 + 12.0,
-                (i as f32 - 3.5) * SQUARE_SIZE + CENTRE_Y,
-            ),
-        );
-        text(
-            &mut commands,
-            style.clone(),
-            &(i + 1).to_string(),
-            Vec2::new(
-                CENTRE_X - SQUARE_SIZE * 4.0 - 12.0,
-                (i as f32 - 3.5) * SQUARE_SIZE + CENTRE_Y,
-            ),
-        );
+        for &y in [
+            CENTRE_Y + SQUARE_SIZE * 4.0 + 13.0,
+            CENTRE_Y - SQUARE_SIZE * 4.0 - 10.0,
+        ]
+        .iter()
+        {
+            let label = text(
+                &mut commands,
+                style.clone(),
+                Vec2::new((i as f32 - 3.5) * SQUARE_SIZE + CENTRE_X, y),
+            );
+            commands.entity(label).insert(Coordinate {
+                file: true,
+                index: i,
+            });
+        }
+        for &x in [
+            CENTRE_X + SQUARE_SIZE * 4.0 + 12.0,
+            CENTRE_X - SQUARE_SIZE * 4.0 - 12.0,
+        ]
+        .iter()
+        {
+            let label = text(
+                &mut commands,
+                style.clone(),
+                Vec2::new(x, (i as f32 - 3.5) * SQUARE_SIZE + CENTRE_Y),
+            );
+            commands.entity(label).insert(Coordinate {
+                file: false,
+                index: i,
+            });
+            if x > CENTRE_X {
+                commands.entity(label).insert(RankLabel);
+            }
+        }
     }
 }
 
-fn text(commands: &mut Commands, style: TextStyle, text: &str, pos: Vec2) {
-    commands.spawn_bundle(Text2dBundle {
-        text: Text::with_section(
-            text,
-            style,
-            TextAlignment {
-                vertical: VerticalAlign::Center,
-                horizontal: HorizontalAlign::Center,
-            },
-        ),
-        transform: Transform::from_xyz(pos.x, pos.y, 0.0),
-        ..Default::default()
-    });
+fn text(commands: &mut Commands, style: TextStyle, pos: Vec2) -> Entity {
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "",
+                style,
+                TextAlignment {
+                    vertical: VerticalAlign::Center,
+                    horizontal: HorizontalAlign::Center,
+                },
+            ),
+            transform: Transform::from_xyz(pos.x, pos.y, 0.0),
+            ..Default::default()
+        })
+        .id()
+}
+
+pub fn update_labels(orientation: Res<Orientation>, mut labels: Query<(&Coordinate, &mut Text)>) {
+    if orientation.is_changed() {
+        for (coordinate, mut text) in labels.iter_mut() {
+            let index = if orientation.flipped {
+                7 - coordinate.index
+            } else {
+                coordinate.index
+            };
+            text.sections[0].value = if coordinate.file {
+                ((b'a' + index as u8) as char).to_string()
+            } else {
+                (index + 1).to_string()
+            };
+        }
+    }
 }
 
 pub fn update(
@@ -192,27 +214,47 @@ pub fn update(
     existing: Query<Entity, With<PieceEntity>>,
     textures: Res<Textures>,
     states: Res<BoardStates>,
+    orientation: Res<Orientation>,
 ) {
-    if states.is_changed() {
-        for entity in existing.iter() {
-            commands.entity(entity).despawn();
-        }
+    if states.is_changed() || orientation.is_changed() {
+        show(
+            &mut commands,
+            &mut pieces,
+            &existing,
+            &textures,
+            &states.active().state.get_pieces(),
+            orientation.flipped,
+        );
+    }
+}
 
-        pieces.0 = HashMap::new();
+// Replaces the pieces on the board with `board`.
+pub fn show(
+    commands: &mut Commands,
+    pieces: &mut PieceEntities,
+    existing: &Query<Entity, With<PieceEntity>>,
+    textures: &Textures,
+    board: &HashMap<Position, Piece>,
+    flipped: bool,
+) {
+    for entity in existing.iter() {
+        commands.entity(entity).despawn();
+    }
 
-        for (pos, piece) in states.active().state.get_pieces().iter() {
-            pieces.0.insert(
-                *pos,
-                commands
-                    .spawn_bundle(SpriteBundle {
-                        material: textures.0.get(piece).unwrap().clone(),
-                        transform: from_board_pos(*pos, 2.0),
-                        ..Default::default()
-                    })
-                    .insert(PieceEntity)
-                    .id(),
-            );
-        }
+    pieces.0 = HashMap::new();
+
+    for (pos, piece) in board.iter() {
+        pieces.0.insert(
+            *pos,
+            commands
+                .spawn_bundle(SpriteBundle {
+                    material: textures.0.get(piece).unwrap().clone(),
+                    transform: from_board_pos(*pos, 2.0, flipped),
+                    ..Default::default()
+                })
+                .insert(PieceEntity)
+                .id(),
+        );
     }
 }
 
@@ -220,6 +262,7 @@ pub fn update_drag(
     mut draggable: Query<&mut Transform, With<PieceEntity>>,
     pieces: Res<PieceEntities>,
     selected: Res<Selected>,
+    orientation: Res<Orientation>,
     mut drag: ResMut<Drag>,
 ) {
     if drag.is_changed() {
@@ -232,7 +275,7 @@ pub fn update_drag(
             }
             Drag::Reset(pos) => {
                 if let Ok(mut transform) = draggable.get_mut(pieces.0[&pos]) {
-                    *transform = from_board_pos(pos, 2.0);
+                    *transform = from_board_pos(pos, 2.0, orientation.flipped);
                 }
                 *drag = Drag::None;
             }
@@ -0,0 +1,248 @@
+use super::*;
+use bevy::ui::FocusPolicy;
+use chess_core::Clock;
+use std::time::Duration;
+
+const ROW_HEIGHT: f32 = 30.0;
+const MARGIN: f32 = 10.0;
+pub const HEIGHT: f32 = MARGIN + ROW_HEIGHT * 3.0;
+const PRESETS: [(&str, Option<&str>); 7] = [
+    ("No clock", None),
+    ("1 min", Some("60")),
+    ("3 min + 2 s", Some("180+2")),
+    ("5 min, 5 s delay", Some("300d5")),
+    ("10 min, 10 s Bronstein", Some("600b10")),
+    ("15 min + 10 s", Some("900+10")),
+    ("40/90 min, 30 min, + 30 s", Some("40/5400+30:1800+30")),
+];
+
+pub struct Clocks {
+    clock: Option<Clock>,
+    preset: usize,
+}
+pub struct ClockButton;
+pub struct ClockText(Colour);
+pub struct TimeControlText;
+pub struct ClockMaterials {
+    active: Handle<ColorMaterial>,
+    inactive: Handle<ColorMaterial>,
+    hovered: Handle<ColorMaterial>,
+}
+
+impl Clocks {
+    pub fn clear(&mut self) {
+        self.clock = None;
+        self.preset = 0;
+    }
+
+    // Sets up a fresh clock for the chosen preset.
+    pub fn restart(&mut self, states: &mut BoardStates) {
+        let control = PRESETS[self.preset].1.and_then(TimeControl::parse);
+        self.clock = control.clone().map(Clock::new);
+        states.time_control = control;
+    }
+
+    pub fn remaining(&self, colour: Colour) -> Option<Duration> {
+        self.clock.as_ref().map(|clock| clock.remaining(colour))
+    }
+}
+
+pub fn press(clocks: &mut Clocks, states: &mut BoardStates, colour: Colour) {
+    if let Some(clock) = &mut clocks.clock {
+        clock.press(colour);
+        states.set_clock(clock.remaining(colour));
+    }
+}
+
+pub fn setup(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    move_list: Res<move_list::MoveList>,
+    font: Res<FontAsset>,
+) {
+    let clock_materials = ClockMaterials {
+        active: materials.add(Color::rgba(0.6, 0.55, 0.3, 1.0).into()),
+        inactive: materials.add(Color::rgba(0.25, 0.24, 0.23, 1.0).into()),
+        hovered: materials.add(Color::rgba(0.4, 0.4, 0.4, 1.0).into()),
+    };
+
+    let mut entities = Vec::new();
+    for (i, &colour) in [Black, White].iter().enumerate() {
+        let entity = commands
+            .spawn_bundle(ButtonBundle {
+                style: Style {
+                    size: Size::new(
+                        Val::Px(PANEL_WIDTH - MARGIN * 2.0),
+                        Val::Px(ROW_HEIGHT - 2.0),
+                    ),
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        left: Val::Px(MARGIN),
+                        bottom: Val::Px(SCREEN_HEIGHT - MARGIN - ROW_HEIGHT * (i + 1) as f32),
+                        ..Default::default()
+                    },
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::Center,
+                    padding: Rect {
+                        left: Val::Px(8.0),
+                        right: Val::Px(8.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                material: clock_materials.inactive.clone(),
+                ..Default::default()
+            })
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle(TextBundle {
+                        text: clock_text(&colour.to_string(), &font),
+                        ..Default::default()
+                    })
+                    .insert(FocusPolicy::Pass);
+                parent
+                    .spawn_bundle(TextBundle {
+                        text: clock_text("-", &font),
+                        ..Default::default()
+                    })
+                    .insert(FocusPolicy::Pass)
+                    .insert(ClockText(colour));
+            })
+            .insert(ClockButton)
+            .id();
+        entities.push(entity);
+    }
+    let caption = commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(MARGIN),
+                    bottom: Val::Px(SCREEN_HEIGHT - MARGIN - ROW_HEIGHT * 3.0 + 6.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                PRESETS[0].0,
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: 16.0,
+                    color: Color::rgba(1.0, 1.0, 1.0, 0.55),
+                },
+                Default::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(TimeControlText)
+        .id();
+    entities.push(caption);
+    commands.entity(move_list.panel()).push_children(&entities);
+
+    commands.insert_resource(Clocks {
+        clock: None,
+        preset: 0,
+    });
+    commands.insert_resource(clock_materials);
+}
+
+fn clock_text(text: &str, font: &FontAsset) -> Text {
+    Text::with_section(
+        text,
+        TextStyle {
+            font: font.0.clone(),
+            font_size: 22.0,
+            color: Color::rgba(1.0, 1.0, 1.0, 0.8),
+        },
+        Default::default(),
+    )
+}
+
+pub fn update(
+    mut clocks: ResMut<Clocks>,
+    mut states: ResMut<BoardStates>,
+    mut game_state: ResMut<State<GameState>>,
+    materials: Res<ClockMaterials>,
+    time: Res<Time>,
+    mut texts: QuerySet<(
+        Query<(&mut Text, &ClockText, &Parent)>,
+        Query<&mut Text, With<TimeControlText>>,
+    )>,
+    mut boxes: Query<(&mut Handle<ColorMaterial>, &Interaction), With<ClockButton>>,
+) {
+    let running = !matches!(
+        game_state.current(),
+        GameState::End | GameState::NewGame | GameState::Setup
+    ) && states.at_end()
+        && states.active().state.ended().is_none();
+    let mut flagged = None;
+    if let Some(clock) = &mut clocks.clock {
+        if running && clock.flagged().is_none() {
+            flagged = clock.tick(time.delta());
+        }
+    }
+    if let Some(colour) = flagged {
+        states.flag_fall(colour);
+        let _ = game_state.overwrite_set(GameState::End);
+    }
+
+    for (mut text, clock_text, parent) in texts.q0_mut().iter_mut() {
+        let colour = clock_text.0;
+        text.sections[0].value = match &clocks.clock {
+            Some(clock) => format_time(clock.remaining(colour)),
+            None => "-".to_string(),
+        };
+        if let Ok((mut material, interaction)) = boxes.get_mut(parent.0) {
+            let active = match &clocks.clock {
+                Some(clock) => clock.started() && clock.turn() == colour && running,
+                None => false,
+            };
+            *material = if active {
+                materials.active.clone()
+            } else if *interaction == Interaction::Hovered && can_change(&clocks, &states) {
+                materials.hovered.clone()
+            } else {
+                materials.inactive.clone()
+            };
+        }
+    }
+    if clocks.is_changed() {
+        for mut text in texts.q1_mut().iter_mut() {
+            text.sections[0].value = PRESETS[clocks.preset].0.to_string();
+        }
+    }
+}
+
+pub fn update_buttons(
+    mut clocks: ResMut<Clocks>,
+    mut states: ResMut<BoardStates>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<ClockButton>)>,
+) {
+    for interaction in buttons.iter() {
+        if *interaction == Interaction::Clicked && can_change(&clocks, &states) {
+            clocks.preset = (clocks.preset + 1) % PRESETS.len();
+            clocks.restart(&mut states);
+        }
+    }
+}
+
+// The time control can only be changed before the first move.
+fn can_change(clocks: &Clocks, states: &BoardStates) -> bool {
+    states.at_start() && states.at_end() && !matches!(&clocks.clock, Some(clock) if clock.started())
+}
+
+fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else if seconds >= 10 {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    } else {
+        format!("0:{:02}.{}", seconds, time.subsec_millis() / 100)
+    }
+}
@@ -0,0 +1,359 @@
+use super::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use chess_core::{Engine, EngineEvent, Go, Limits, Params, SearchResult, Searcher, UciError};
+use futures_lite::future;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// The greatest depth and the thinking time in milliseconds at each level.
+const LEVELS: [(u32, u64); 6] = [
+    (1, 100),
+    (2, 200),
+    (3, 400),
+    (4, 800),
+    (6, 1500),
+    (32, 3000),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Player {
+    Human,
+    Computer(usize),
+    Engine(usize),
+}
+
+impl Player {
+    pub fn name(&self, computer: &Computer) -> String {
+        match *self {
+            Player::Human => "Human".to_string(),
+            Player::Computer(level) => format!("Computer (level {})", level + 1),
+            Player::Engine(index) => computer.engines[index].name.clone(),
+        }
+    }
+
+    // The next choice in the new game dialog, going from human through each
+    // level of computer and each external engine and back.
+    pub fn next(&self, computer: &Computer) -> Player {
+        match *self {
+            Player::Human => Player::Computer(0),
+            Player::Computer(level) if level + 1 < LEVELS.len() => Player::Computer(level + 1),
+            Player::Computer(_) if !computer.engines.is_empty() => Player::Engine(0),
+            Player::Engine(index) if index + 1 < computer.engines.len() => {
+                Player::Engine(index + 1)
+            }
+            _ => Player::Human,
+        }
+    }
+}
+
+pub struct Players(pub [Player; 2]);
+
+impl Players {
+    pub fn get(&self, colour: Colour) -> Player {
+        self.0[colour as usize]
+    }
+}
+
+// An external UCI engine listed in engines.txt, which looks like this:
+//
+//     [Stockfish]
+//     path = /usr/local/bin/stockfish
+//     Hash = 64
+//
+// Any lines other than the path set the engine's options.
+struct EngineConfig {
+    name: String,
+    path: String,
+    options: Vec<(String, String)>,
+}
+
+// The search runs on the async compute pool and hands the searcher back when
+// it is done, so that its transposition table is kept between moves. External
+// engines are started the first time they are needed and kept running.
+pub struct Computer {
+    searcher: Option<Searcher>,
+    task: Option<Task<(Searcher, SearchResult)>>,
+    position: (usize, u64),
+    stop: Arc<AtomicBool>,
+    params: Params,
+    engines: Vec<EngineConfig>,
+    running: Vec<Option<Engine>>,
+    thinking: Option<usize>,
+}
+
+impl Computer {
+    fn new(params: Params, engines: Vec<EngineConfig>) -> Computer {
+        let mut searcher = Searcher::new();
+        searcher.set_params(params.clone());
+        Computer {
+            stop: searcher.stop_handle(),
+            params,
+            searcher: Some(searcher),
+            task: None,
+            position: (0, 0),
+            running: engines.iter().map(|_| None).collect(),
+            engines,
+            thinking: None,
+        }
+    }
+
+    // Abandons any search in progress and forgets the last game.
+    pub fn reset(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.stop_engine();
+        let mut searcher = Searcher::new();
+        searcher.set_params(self.params.clone());
+        self.stop = searcher.stop_handle();
+        self.searcher = Some(searcher);
+        self.task = None;
+        self.position = (0, 0);
+        for engine in self.running.iter_mut() {
+            if let Some(Err(_)) = engine.as_mut().map(Engine::new_game) {
+                *engine = None;
+            }
+        }
+    }
+
+    // Starts the engine if it is not running yet and sets it thinking about
+    // the current position, on the clock if there is one.
+    fn start_engine(
+        &mut self,
+        index: usize,
+        states: &BoardStates,
+        clocks: &clock::Clocks,
+    ) -> Result<(), UciError> {
+        if self.running[index].is_none() {
+            let config = &self.engines[index];
+            let mut engine = Engine::start(&config.path)?;
+            for (name, value) in config.options.iter() {
+                if let Err(error) = engine.set_option(name, value) {
+                    eprintln!("{}: {}", config.name, error);
+                }
+            }
+            engine.new_game()?;
+            self.running[index] = Some(engine);
+        }
+        let engine = self.running[index].as_mut().unwrap();
+        let (start, moves) = states.moves();
+        engine.set_position(&start, &moves)?;
+        let go = match (clocks.remaining(White), clocks.remaining(Black)) {
+            (Some(white), Some(black)) => Go {
+                clock: Some([white, black]),
+                ..Default::default()
+            },
+            _ => Go {
+                move_time: Some(Duration::from_millis(LEVELS[LEVELS.len() - 1].1)),
+                ..Default::default()
+            },
+        };
+        engine.go(&go)?;
+        self.thinking = Some(index);
+        Ok(())
+    }
+
+    // The engine's move once it has finished thinking.
+    fn poll_engine(&mut self) -> Result<Option<Move>, UciError> {
+        let index = match self.thinking {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        if let Some(engine) = &mut self.running[index] {
+            while let Some(event) = engine.poll()? {
+                if let EngineEvent::BestMove { best, .. } = event {
+                    self.thinking = None;
+                    return Ok(best);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // Stops the engine's search and waits for its answer, so that it is not
+    // taken as the answer to the next one.
+    fn stop_engine(&mut self) {
+        if let Some(index) = self.thinking.take() {
+            if let Some(engine) = &mut self.running[index] {
+                if finish(engine).is_err() {
+                    self.running[index] = None;
+                }
+            }
+        }
+    }
+
+    // Gives up on an engine that has stopped working, leaving its side to be
+    // played by hand.
+    fn engine_failed(
+        &mut self,
+        index: usize,
+        players: &mut Players,
+        colour: Colour,
+        error: UciError,
+    ) {
+        eprintln!("{}: {}", self.engines[index].name, error);
+        self.running[index] = None;
+        self.thinking = None;
+        players.0[colour as usize] = Player::Human;
+    }
+}
+
+fn finish(engine: &mut Engine) -> Result<(), UciError> {
+    engine.stop()?;
+    loop {
+        if let EngineEvent::BestMove { .. } = engine.wait(Duration::from_secs(1))? {
+            return Ok(());
+        }
+    }
+}
+
+pub fn setup(mut commands: Commands) {
+    commands.insert_resource(Players([Player::Human; 2]));
+    commands.insert_resource(Computer::new(load_params(), load_engines()));
+}
+
+// Evaluation weights are read from eval.txt if there is one, such as the
+// output of the tune tool in chess-core.
+fn load_params() -> Params {
+    match fs::read_to_string("eval.txt") {
+        Ok(text) => Params::parse(&text).unwrap_or_else(|error| {
+            eprintln!("eval.txt: {}", error);
+            Params::default()
+        }),
+        Err(_) => Params::default(),
+    }
+}
+
+fn load_engines() -> Vec<EngineConfig> {
+    let text = match fs::read_to_string("engines.txt") {
+        Ok(text) => text,
+        Err(_) => return Vec::new(),
+    };
+    let mut engines: Vec<EngineConfig> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            engines.push(EngineConfig {
+                name: line[1..line.len() - 1].trim().to_string(),
+                path: String::new(),
+                options: Vec::new(),
+            });
+        } else if let (Some(engine), Some(equals)) = (engines.last_mut(), line.find('=')) {
+            let (key, value) = (line[..equals].trim(), line[equals + 1..].trim());
+            if key == "path" {
+                engine.path = value.to_string();
+            } else {
+                engine.options.push((key.to_string(), value.to_string()));
+            }
+        } else {
+            eprintln!(
+                "engines.txt: line {}: expected [name] or key = value",
+                i + 1
+            );
+        }
+    }
+    engines.retain(|engine| {
+        if engine.path.is_empty() {
+            eprintln!("engines.txt: {} has no path", engine.name);
+        }
+        !engine.path.is_empty()
+    });
+    engines
+}
+
+// Whether the computer is to play the next move. It only plays at the end of
+// a line, so that earlier positions can still be looked through.
+pub fn to_move(players: &Players, states: &BoardStates) -> bool {
+    let state = &states.active().state;
+    states.at_end()
+        && state.ended().is_none()
+        && !state.promoting()
+        && players.get(state.get_turn()) != Player::Human
+}
+
+pub fn update(
+    mut computer: ResMut<Computer>,
+    mut states: ResMut<BoardStates>,
+    mut clocks: ResMut<clock::Clocks>,
+    mut game_state: ResMut<State<GameState>>,
+    mut players: ResMut<Players>,
+    pool: Res<AsyncComputeTaskPool>,
+) {
+    let computer = &mut *computer;
+    let position = (states.current(), states.active().state.hash());
+    let colour = states.active().state.get_turn();
+
+    if let Some(task) = &mut computer.task {
+        let (searcher, result) = match future::block_on(future::poll_once(task)) {
+            Some(output) => output,
+            None => {
+                if computer.position != position {
+                    computer.stop.store(true, Ordering::Relaxed);
+                }
+                return;
+            }
+        };
+        computer.task = None;
+        computer.searcher = Some(searcher);
+        if computer.position == position && to_move(&players, &states) {
+            if let Some(mv) = result.best_move {
+                play_move(&mut states, &mut clocks, &mut game_state, mv);
+            }
+        }
+        return;
+    }
+
+    if let Some(index) = computer.thinking {
+        if computer.position != position {
+            computer.stop_engine();
+            return;
+        }
+        match computer.poll_engine() {
+            Ok(Some(mv)) if to_move(&players, &states) => {
+                play_move(&mut states, &mut clocks, &mut game_state, mv);
+            }
+            Ok(_) => (),
+            Err(error) => computer.engine_failed(index, &mut players, colour, error),
+        }
+        return;
+    }
+
+    if !to_move(&players, &states) {
+        return;
+    }
+    let state = states.active().state.clone();
+    let (depth, time) = match players.get(colour) {
+        Player::Computer(level) => LEVELS[level],
+        Player::Engine(index) => {
+            computer.position = position;
+            if let Err(error) = computer.start_engine(index, &states, &clocks) {
+                computer.engine_failed(index, &mut players, colour, error);
+            }
+            return;
+        }
+        Player::Human => return,
+    };
+    let mut time = Duration::from_millis(time);
+    if let Some(remaining) = clocks.remaining(colour) {
+        time = time.min(remaining / 30);
+    }
+
+    if let Some(mut searcher) = computer.searcher.take() {
+        searcher.set_history(&states.history());
+        computer.position = position;
+        computer.task = Some(pool.spawn(async move {
+            let result = searcher.search(
+                &state,
+                Limits {
+                    depth: Some(depth),
+                    time: Some(time),
+                    ..Default::default()
+                },
+            );
+            (searcher, result)
+        }));
+    }
+}
@@ -0,0 +1,767 @@
+use super::*;
+use bevy::ui::FocusPolicy;
+
+const MARGIN: f32 = 10.0;
+const WIDE: f32 = PANEL_WIDTH - MARGIN * 2.0;
+const NARROW: f32 = (WIDE - MARGIN) / 2.0;
+const BUTTON_HEIGHT: f32 = 36.0;
+const PALETTE_SIZE: f32 = 42.0;
+const PALETTE: [Variant; 6] = [King, Queen, Rook, Bishop, Knight, Pawn];
+
+// A position being set up, which only has to be legal once play starts.
+pub struct Editor {
+    pieces: HashMap<Position, Piece>,
+    turn: Colour,
+    // White's king and queen side, then Black's, standing for the outermost
+    // rook on each side of the king.
+    castling: [bool; 4],
+    en_passant: Option<Position>,
+    rules: &'static dyn chess_core::Rules,
+    // The position last loaded, whose promoted pieces and Chess960 castling
+    // rights are kept in the FEN, along with its clocks, pockets and check
+    // counts until the board is edited.
+    loaded: chess_core::State,
+    edited: bool,
+    // The palette piece placed on each square clicked.
+    palette: Option<Piece>,
+    // A piece picked up off the board, and the square it came from.
+    carried: Option<(Piece, Position)>,
+    // Why copying or pasting failed.
+    message: Option<String>,
+    panel: Entity,
+    ghost: Entity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorButton {
+    Piece(Piece),
+    Turn,
+    Castle(usize),
+    EnPassant,
+    Clear,
+    Reset,
+    Copy,
+    Paste,
+    Play,
+    Cancel,
+}
+pub struct FenText;
+pub struct ErrorText;
+pub struct Ghost;
+
+pub struct EditorMaterials {
+    background: Handle<ColorMaterial>,
+    normal: Handle<ColorMaterial>,
+    hovered: Handle<ColorMaterial>,
+    on: Handle<ColorMaterial>,
+    greyed: Handle<ColorMaterial>,
+}
+
+impl Editor {
+    fn load(&mut self, state: &chess_core::State) {
+        self.pieces = state.get_pieces();
+        self.turn = state.get_turn();
+        self.castling = [
+            state.can_castle(White, true),
+            state.can_castle(White, false),
+            state.can_castle(Black, true),
+            state.can_castle(Black, false),
+        ];
+        self.en_passant = state.en_passant();
+        self.loaded = if state.rules().name() == self.rules.name() {
+            state.clone()
+        } else {
+            chess_core::State::with_rules(self.rules)
+        };
+        self.edited = false;
+    }
+
+    fn fen(&self) -> String {
+        let loaded = self.loaded.to_fen();
+        let fields: Vec<&str> = loaded.split(' ').collect();
+        let board = fields[0].split('[').next().unwrap();
+        // An edited position starts with empty pockets and fresh clocks and
+        // check counts, as in the rules' starting position.
+        let fresh = chess_core::State::with_rules(self.rules).to_fen();
+        let extra: Vec<&str> = if self.edited {
+            fresh.split(' ').collect()
+        } else {
+            fields.clone()
+        };
+        let pockets = &extra[0][extra[0].find('[').unwrap_or(extra[0].len())..];
+
+        // A promoted piece stays marked as long as it has not been moved.
+        let mut promoted = HashSet::new();
+        for (rank, y) in board.split('/').zip((0..8).rev()) {
+            let mut x = 0;
+            for c in rank.chars() {
+                match c {
+                    '~' => {
+                        promoted.insert(Position::from_xy(x - 1, y).unwrap());
+                    }
+                    '1'..='8' => x += c as i8 - b'0' as i8,
+                    _ => x += 1,
+                }
+            }
+        }
+
+        let mut fen = String::new();
+        for y in (0..8).rev() {
+            let mut empty = 0;
+            for x in 0..8 {
+                let pos = Position::from_xy(x, y).unwrap();
+                match self.pieces.get(&pos) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece.to_char());
+                        if promoted.contains(&pos) && self.loaded.get_piece(pos) == Some(*piece) {
+                            fen.push('~');
+                        }
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if y > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(pockets);
+
+        fen.push_str(if self.turn == White { " w " } else { " b " });
+        // Rights that were already there keep their letter, which names the
+        // rook's file when it is not the outermost.
+        let mut letters = fields[2].chars().filter(|&c| c != '-');
+        let castling: String = "KQkq"
+            .chars()
+            .enumerate()
+            .filter_map(|(right, c)| {
+                let colour = if right < 2 { White } else { Black };
+                let letter = if self.loaded.can_castle(colour, right % 2 == 0) {
+                    letters.next()
+                } else {
+                    None
+                };
+                Some(letter.unwrap_or(c)).filter(|_| self.castling[right])
+            })
+            .collect();
+        fen.push_str(if castling.is_empty() { "-" } else { &castling });
+        fen.push(' ');
+        match self.en_passant {
+            Some(pos) => fen.push_str(&pos.to_string()),
+            None => fen.push('-'),
+        }
+        for field in extra[4..].iter() {
+            fen.push(' ');
+            fen.push_str(field);
+        }
+        fen
+    }
+
+    fn position(&self) -> Result<chess_core::State, chess_core::FenError> {
+        chess_core::State::from_fen_with_rules(&self.fen(), self.rules)
+    }
+
+    // The squares behind a pawn of the side not to move that could have just
+    // moved two squares.
+    fn en_passant_squares(&self) -> Vec<Position> {
+        let (rank, pawn) = if self.turn == White { (5, 4) } else { (2, 3) };
+        let pushed = Piece {
+            colour: self.turn.flipped(),
+            variant: Pawn,
+        };
+        (0..8)
+            .map(|x| Position::from_xy(x, rank).unwrap())
+            .filter(|pos| {
+                !self.pieces.contains_key(pos)
+                    && self
+                        .pieces
+                        .get(&Position::from_xy(pos.get_x(), pawn).unwrap())
+                        == Some(&pushed)
+            })
+            .collect()
+    }
+
+    fn set(&mut self, pos: Position, piece: Option<Piece>) {
+        match piece {
+            Some(piece) => self.pieces.insert(pos, piece),
+            None => self.pieces.remove(&pos),
+        };
+        self.edited = true;
+        self.check_en_passant();
+    }
+
+    fn check_en_passant(&mut self) {
+        if let Some(pos) = self.en_passant {
+            if !self.en_passant_squares().contains(&pos) {
+                self.en_passant = None;
+            }
+        }
+    }
+
+    fn copy(&mut self) {
+        let fen = self.fen();
+        if let Err(error) =
+            arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(fen))
+        {
+            self.message = Some(error.to_string());
+        }
+    }
+
+    fn paste(&mut self) {
+        let text = match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+            Ok(text) => text,
+            Err(error) => {
+                self.message = Some(error.to_string());
+                return;
+            }
+        };
+        match chess_core::State::from_fen_with_rules(text.trim(), self.rules) {
+            Ok(state) => self.load(&state),
+            Err(error) => self.message = Some(error.to_string()),
+        }
+    }
+}
+
+pub fn setup(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    commands.insert_resource(EditorMaterials {
+        background: materials.add(Color::rgb(0.15, 0.14, 0.13).into()),
+        normal: materials.add(Color::rgba(0.4, 0.4, 0.4, 1.0).into()),
+        hovered: materials.add(Color::rgba(0.6, 0.6, 0.6, 1.0).into()),
+        on: materials.add(Color::rgba(0.6, 0.55, 0.3, 1.0).into()),
+        greyed: materials.add(Color::rgba(0.21, 0.2, 0.19, 1.0).into()),
+    });
+}
+
+// The editor covers the move list, leaving the clocks above it.
+pub fn setup_editor(
+    mut commands: Commands,
+    materials: Res<EditorMaterials>,
+    textures: Res<board::Textures>,
+    font: Res<FontAsset>,
+    states: Res<BoardStates>,
+    new_game: Res<ui::NewGame>,
+) {
+    let panel = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(PANEL_WIDTH), Val::Px(SCREEN_HEIGHT - clock::HEIGHT)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(SCREEN_WIDTH - PANEL_WIDTH),
+                    bottom: Val::Px(0.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material: materials.background.clone(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn_bundle(text(&font, "Set up position", 26.0, MARGIN));
+            for (row, &colour) in [White, Black].iter().enumerate() {
+                for (i, &variant) in PALETTE.iter().enumerate() {
+                    let piece = Piece { colour, variant };
+                    piece_button(
+                        parent,
+                        &materials,
+                        textures.0.get(&piece).unwrap().clone(),
+                        Rect {
+                            top: Val::Px(50.0 + (PALETTE_SIZE + 4.0) * row as f32),
+                            left: Val::Px(MARGIN + WIDE / 6.0 * i as f32),
+                            ..Default::default()
+                        },
+                        piece,
+                    );
+                }
+            }
+
+            let rows = [
+                (150.0, EditorButton::Turn, None),
+                (
+                    196.0,
+                    EditorButton::Castle(0),
+                    Some(EditorButton::Castle(1)),
+                ),
+                (
+                    238.0,
+                    EditorButton::Castle(2),
+                    Some(EditorButton::Castle(3)),
+                ),
+                (284.0, EditorButton::EnPassant, None),
+                (334.0, EditorButton::Clear, Some(EditorButton::Reset)),
+                (376.0, EditorButton::Copy, Some(EditorButton::Paste)),
+            ];
+            for &(top, left, right) in rows.iter() {
+                let width = if right.is_some() { NARROW } else { WIDE };
+                text_button(
+                    parent,
+                    &materials,
+                    &font,
+                    Rect {
+                        top: Val::Px(top),
+                        left: Val::Px(MARGIN),
+                        ..Default::default()
+                    },
+                    width,
+                    left,
+                );
+                if let Some(right) = right {
+                    text_button(
+                        parent,
+                        &materials,
+                        &font,
+                        Rect {
+                            top: Val::Px(top),
+                            right: Val::Px(MARGIN),
+                            ..Default::default()
+                        },
+                        width,
+                        right,
+                    );
+                }
+            }
+
+            parent
+                .spawn_bundle(text(&font, "", 14.0, 426.0))
+                .insert(FenText);
+            parent
+                .spawn_bundle(text(&font, "", 16.0, 470.0))
+                .insert(ErrorText);
+
+            text_button(
+                parent,
+                &materials,
+                &font,
+                Rect {
+                    bottom: Val::Px(MARGIN),
+                    left: Val::Px(MARGIN),
+                    ..Default::default()
+                },
+                NARROW,
+                EditorButton::Play,
+            );
+            text_button(
+                parent,
+                &materials,
+                &font,
+                Rect {
+                    bottom: Val::Px(MARGIN),
+                    right: Val::Px(MARGIN),
+                    ..Default::default()
+                },
+                NARROW,
+                EditorButton::Cancel,
+            );
+        })
+        .id();
+
+    let ghost = commands
+        .spawn_bundle(SpriteBundle {
+            visible: Visible {
+                is_visible: false,
+                is_transparent: true,
+            },
+            ..Default::default()
+        })
+        .insert(Ghost)
+        .id();
+
+    let mut editor = Editor {
+        pieces: HashMap::new(),
+        turn: White,
+        castling: [false; 4],
+        en_passant: None,
+        rules: new_game.rules(),
+        loaded: chess_core::State::new(),
+        edited: false,
+        palette: None,
+        carried: None,
+        message: None,
+        panel,
+        ghost,
+    };
+    editor.load(&states.active().state);
+    commands.insert_resource(editor);
+}
+
+fn text(font: &FontAsset, text: &str, font_size: f32, top: f32) -> TextBundle {
+    TextBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                top: Val::Px(top),
+                left: Val::Px(MARGIN),
+                ..Default::default()
+            },
+            max_size: Size::new(Val::Px(WIDE), Val::Undefined),
+            ..Default::default()
+        },
+        text: Text::with_section(
+            text,
+            TextStyle {
+                font: font.0.clone(),
+                font_size,
+                color: Color::rgba(1.0, 1.0, 1.0, 0.7),
+            },
+            Default::default(),
+        ),
+        ..Default::default()
+    }
+}
+
+fn piece_button(
+    parent: &mut ChildBuilder,
+    materials: &EditorMaterials,
+    texture: Handle<ColorMaterial>,
+    position: Rect<Val>,
+    piece: Piece,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(PALETTE_SIZE), Val::Px(PALETTE_SIZE)),
+                position_type: PositionType::Absolute,
+                position,
+                ..Default::default()
+            },
+            material: materials.normal.clone(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(ImageBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(PALETTE_SIZE - 2.0), Val::Px(PALETTE_SIZE - 2.0)),
+                        margin: Rect::all(Val::Auto),
+                        ..Default::default()
+                    },
+                    material: texture,
+                    ..Default::default()
+                })
+                .insert(FocusPolicy::Pass);
+        })
+        .insert(EditorButton::Piece(piece));
+}
+
+fn text_button(
+    parent: &mut ChildBuilder,
+    materials: &EditorMaterials,
+    font: &FontAsset,
+    position: Rect<Val>,
+    width: f32,
+    button: EditorButton,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(width), Val::Px(BUTTON_HEIGHT)),
+                position_type: PositionType::Absolute,
+                position,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.normal.clone(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: 18.0,
+                            color: Color::rgba(1.0, 1.0, 1.0, 0.7),
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(FocusPolicy::Pass)
+                .insert(button);
+        })
+        .insert(button);
+}
+
+fn label(button: EditorButton, editor: &Editor) -> String {
+    match button {
+        EditorButton::Piece(_) => String::new(),
+        EditorButton::Turn => format!("{} to move", editor.turn),
+        EditorButton::Castle(right) => format!(
+            "{} {}",
+            if right < 2 { White } else { Black },
+            if right % 2 == 0 { "O-O" } else { "O-O-O" }
+        ),
+        EditorButton::EnPassant => match editor.en_passant {
+            Some(pos) => format!("En passant on {}", pos),
+            None => "No en passant".to_string(),
+        },
+        EditorButton::Clear => "Clear".to_string(),
+        EditorButton::Reset => "Start position".to_string(),
+        EditorButton::Copy => "Copy FEN".to_string(),
+        EditorButton::Paste => "Paste FEN".to_string(),
+        EditorButton::Play => "Play".to_string(),
+        EditorButton::Cancel => "Cancel".to_string(),
+    }
+}
+
+pub fn update_buttons(
+    mut game_state: ResMut<State<GameState>>,
+    mut editor: ResMut<Editor>,
+    mut states: ResMut<BoardStates>,
+    mut clocks: ResMut<clock::Clocks>,
+    mut computer: ResMut<computer::Computer>,
+    mut players: ResMut<computer::Players>,
+    mut selected: ResMut<Selected>,
+    mut windows: ResMut<Windows>,
+    new_game: Res<ui::NewGame>,
+    buttons: Query<(&Interaction, &EditorButton), (Changed<Interaction>, With<Button>)>,
+) {
+    for (interaction, &button) in buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+        editor.message = None;
+        match button {
+            EditorButton::Piece(piece) => {
+                editor.palette = if editor.palette == Some(piece) {
+                    None
+                } else {
+                    Some(piece)
+                };
+            }
+            EditorButton::Turn => {
+                editor.turn = editor.turn.flipped();
+                editor.check_en_passant();
+            }
+            EditorButton::Castle(right) => {
+                editor.castling[right] = !editor.castling[right];
+            }
+            EditorButton::EnPassant => {
+                let squares = editor.en_passant_squares();
+                let current = editor
+                    .en_passant
+                    .and_then(|pos| squares.iter().position(|&square| square == pos));
+                editor.en_passant = match current {
+                    Some(i) => squares.get(i + 1).copied(),
+                    None => squares.first().copied(),
+                };
+            }
+            EditorButton::Clear => {
+                editor.pieces.clear();
+                editor.castling = [false; 4];
+                editor.en_passant = None;
+                editor.edited = true;
+            }
+            EditorButton::Reset => {
+                let rules = editor.rules;
+                editor.load(&chess_core::State::with_rules(rules));
+            }
+            EditorButton::Copy => editor.copy(),
+            EditorButton::Paste => editor.paste(),
+            EditorButton::Play => {
+                if let Ok(state) = editor.position() {
+                    let ended = state.ended().is_some();
+                    players.0 = new_game.players;
+                    *states = BoardStates::from_state(state);
+                    clocks.restart(&mut states);
+                    computer.reset();
+                    selected.0 = None;
+                    windows
+                        .get_primary_mut()
+                        .unwrap()
+                        .set_title("Chess".to_string());
+                    if ended {
+                        game_state.set(GameState::End).unwrap();
+                    } else {
+                        game_state.set(GameState::Playing).unwrap();
+                    }
+                }
+            }
+            EditorButton::Cancel => {
+                if states.active().state.ended().is_some() {
+                    game_state.set(GameState::End).unwrap();
+                } else {
+                    game_state.set(GameState::Playing).unwrap();
+                }
+            }
+        }
+    }
+}
+
+// Pieces are placed by clicking or dragging from the palette, moved by
+// dragging, and removed by right-clicking or dragging them off the board.
+pub fn update_input(
+    mut editor: ResMut<Editor>,
+    windows: Res<Windows>,
+    mouse_input: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    textures: Res<board::Textures>,
+    orientation: Res<Orientation>,
+    mut ghost: Query<(&mut Transform, &mut Visible, &mut Handle<ColorMaterial>), With<Ghost>>,
+) {
+    let control = keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl);
+    if control && keys.just_pressed(KeyCode::C) {
+        editor.copy();
+    }
+    if control && keys.just_pressed(KeyCode::V) {
+        editor.paste();
+    }
+
+    let window = windows.get_primary().unwrap();
+    let mouse_pos = if let Some(pos) = window.cursor_position() {
+        pos - Vec2::new(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0)
+    } else {
+        return;
+    };
+    let pos = to_board_pos(mouse_pos, orientation.flipped);
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        if let Some(pos) = pos {
+            if let Some(piece) = editor.palette {
+                editor.set(pos, Some(piece));
+            } else if let Some(&piece) = editor.pieces.get(&pos) {
+                editor.set(pos, None);
+                editor.carried = Some((piece, pos));
+            }
+        }
+    } else if mouse_input.just_released(MouseButton::Left) {
+        if let Some((piece, _)) = editor.carried {
+            editor.carried = None;
+            if let Some(pos) = pos {
+                editor.set(pos, Some(piece));
+            }
+        } else if let (Some(piece), Some(pos)) = (editor.palette, pos) {
+            if editor.pieces.get(&pos) != Some(&piece) {
+                editor.set(pos, Some(piece));
+            }
+        }
+    }
+    if mouse_input.just_pressed(MouseButton::Right) {
+        if let Some((piece, from)) = editor.carried {
+            editor.carried = None;
+            editor.set(from, Some(piece));
+        } else if let Some(pos) = pos {
+            if editor.pieces.contains_key(&pos) {
+                editor.set(pos, None);
+            }
+        } else if editor.palette.is_some() {
+            editor.palette = None;
+        }
+    }
+
+    let held = match editor.carried {
+        Some((piece, _)) => Some(piece),
+        None => editor
+            .palette
+            .filter(|_| mouse_input.pressed(MouseButton::Left)),
+    };
+    if let Ok((mut transform, mut visible, mut material)) = ghost.single_mut() {
+        visible.is_visible = held.is_some();
+        if let Some(piece) = held {
+            *transform = Transform::from_xyz(mouse_pos.x, mouse_pos.y, 3.0);
+            *material = textures.0.get(&piece).unwrap().clone();
+        }
+    }
+}
+
+pub fn update_panel(
+    editor: Res<Editor>,
+    materials: Res<EditorMaterials>,
+    mut valid: Local<bool>,
+    mut buttons: Query<(&Interaction, &mut Handle<ColorMaterial>, &EditorButton), With<Button>>,
+    mut labels: Query<(&mut Text, &EditorButton), Without<Button>>,
+    mut fen_text: Query<&mut Text, (With<FenText>, Without<EditorButton>)>,
+    mut error_text: Query<&mut Text, (With<ErrorText>, Without<EditorButton>, Without<FenText>)>,
+) {
+    if editor.is_changed() {
+        let error = editor.position().err();
+        *valid = error.is_none();
+        let message = match (&editor.message, error) {
+            (Some(message), _) => message.clone(),
+            (None, Some(error)) => error.kind.to_string(),
+            (None, None) => String::new(),
+        };
+        for (mut text, &button) in labels.iter_mut() {
+            text.sections[0].value = label(button, &editor);
+        }
+        for mut text in fen_text.iter_mut() {
+            text.sections[0].value = editor.fen();
+        }
+        for mut text in error_text.iter_mut() {
+            text.sections[0].value = message.clone();
+        }
+    }
+
+    for (interaction, mut material, &button) in buttons.iter_mut() {
+        let on = match button {
+            EditorButton::Piece(piece) => editor.palette == Some(piece),
+            EditorButton::Castle(right) => editor.castling[right],
+            _ => false,
+        };
+        let new = if button == EditorButton::Play && !*valid {
+            &materials.greyed
+        } else if *interaction != Interaction::None {
+            &materials.hovered
+        } else if on {
+            &materials.on
+        } else {
+            &materials.normal
+        };
+        if *material != *new {
+            *material = new.clone();
+        }
+    }
+}
+
+pub fn update_board(
+    mut commands: Commands,
+    mut pieces: ResMut<board::PieceEntities>,
+    existing: Query<Entity, With<board::PieceEntity>>,
+    textures: Res<board::Textures>,
+    editor: Res<Editor>,
+    orientation: Res<Orientation>,
+) {
+    if editor.is_changed() || orientation.is_changed() {
+        board::show(
+            &mut commands,
+            &mut pieces,
+            &existing,
+            &textures,
+            &editor.pieces,
+            orientation.flipped,
+        );
+    }
+}
+
+pub fn destruct_editor(
+    mut commands: Commands,
+    editor: Res<Editor>,
+    mut pieces: ResMut<board::PieceEntities>,
+    existing: Query<Entity, With<board::PieceEntity>>,
+    textures: Res<board::Textures>,
+    states: Res<BoardStates>,
+    orientation: Res<Orientation>,
+    mut highlights: ResMut<Highlights>,
+) {
+    commands.entity(editor.panel).despawn_recursive();
+    commands.entity(editor.ghost).despawn();
+    commands.remove_resource::<Editor>();
+    board::show(
+        &mut commands,
+        &mut pieces,
+        &existing,
+        &textures,
+        &states.active().state.get_pieces(),
+        orientation.flipped,
+    );
+    highlights.selected_move = None;
+}
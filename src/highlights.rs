@@ -60,23 +60,35 @@ pub fn setup(
 }
 
 pub fn update(
-    mut highlights: Query<(&Highlight, &mut Handle<ColorMaterial>)>,
+    mut highlights: Query<(&Highlight, &mut Handle<ColorMaterial>, &mut Transform)>,
     materials: Res<HighlightTextures>,
     states: Res<BoardStates>,
     mut highlight_state: ResMut<Highlights>,
     selected: Res<Selected>,
+    dropping: Res<pocket::Dropping>,
     piece_entities: Res<PieceEntities>,
+    orientation: Res<Orientation>,
 ) {
     let king = states.active().state.king_in_check();
 
-    if highlight_state.is_changed() || selected.is_changed() || states.is_changed() {
+    if highlight_state.is_changed()
+        || selected.is_changed()
+        || dropping.is_changed()
+        || states.is_changed()
+        || orientation.is_changed()
+    {
         highlight_state.selected_piece_moves = HashSet::new();
         if let Some(pos) = selected.0 {
             highlight_state.selected_piece_moves =
                 states.active().piece_moves.get(&pos).unwrap().clone();
+        } else if let Some(variant) = dropping.variant {
+            highlight_state.selected_piece_moves = states.active().state.drop_squares(variant);
         }
 
-        for (Highlight(pos), mut material) in highlights.iter_mut() {
+        for (Highlight(pos), mut material, mut transform) in highlights.iter_mut() {
+            if orientation.is_changed() {
+                *transform = from_board_pos(*pos, 1.0, orientation.flipped);
+            }
             if let Some(m) = highlight_state.selected_move {
                 if m == *pos {
                     *material = materials.hovering_move.clone();
@@ -113,3 +125,13 @@ pub fn update(
         }
     }
 }
+
+// Clears the board of highlights while a position is being set up.
+pub fn clear(
+    mut highlights: Query<&mut Handle<ColorMaterial>, With<Highlight>>,
+    materials: Res<HighlightTextures>,
+) {
+    for mut material in highlights.iter_mut() {
+        *material = materials.none.clone();
+    }
+}
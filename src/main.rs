@@ -1,14 +1,22 @@
 #![windows_subsystem = "windows"]
 
 mod board;
-mod chess;
+mod clock;
+mod computer;
+mod editor;
 mod highlights;
+mod move_list;
+mod pgn;
+mod pocket;
 mod states;
 mod ui;
 
 use bevy::{prelude::*, render::pass::ClearColor};
 use board::{Drag, PieceEntities, Textures};
-use chess::{Colour, Colour::*, EndState, Piece, Position, Variant, Variant::*};
+use chess_core::{
+    Colour, Colour::*, EndState, Game, Move, MoveKind, Node, Piece, Position, TimeControl, Variant,
+    Variant::*,
+};
 use highlights::Highlights;
 use states::BoardStates;
 use std::collections::{HashMap, HashSet};
@@ -18,12 +26,15 @@ pub enum GameState {
     Playing,
     Promoting,
     End,
+    NewGame,
+    Setup,
 }
 
 const SQUARE_SIZE: f32 = 64.0;
-const SCREEN_WIDTH: f32 = 600.0;
+const PANEL_WIDTH: f32 = 280.0;
+const SCREEN_WIDTH: f32 = 600.0 + PANEL_WIDTH;
 const SCREEN_HEIGHT: f32 = 660.0;
-const CENTRE_X: f32 = 0.0;
+const CENTRE_X: f32 = -PANEL_WIDTH / 2.0;
 const CENTRE_Y: f32 = 30.0;
 
 fn main() {
@@ -44,8 +55,21 @@ fn main() {
         .add_startup_system(ui::setup.system().after("setup"))
         .add_startup_system(highlights::setup.system())
         .add_startup_system(board::setup.system())
+        .add_startup_system(pgn::setup.system())
+        .add_startup_system(move_list::setup.system())
+        .add_startup_system(computer::setup.system())
+        .add_startup_system(editor::setup.system())
+        .add_system(pgn::update.system())
+        .add_startup_system_to_stage(StartupStage::PostStartup, clock::setup.system())
+        .add_startup_system_to_stage(StartupStage::PostStartup, pocket::setup.system())
+        .add_system(move_list::update.system())
+        .add_system(clock::update.system())
+        .add_system(board::update_labels.system())
         .add_system_set(
             SystemSet::on_update(GameState::Playing)
+                .with_system(ui::update_keys.system().before("buttons"))
+                .with_system(move_list::update_buttons.system().before("buttons"))
+                .with_system(clock::update_buttons.system().before("buttons"))
                 .with_system(ui::update_greyed.system().before("buttons"))
                 .with_system(
                     ui::update_buttons
@@ -53,10 +77,13 @@ fn main() {
                         .label("buttons")
                         .before("update"),
                 )
+                .with_system(update_orientation.system().before("update"))
                 .with_system(update.system().label("update"))
+                .with_system(computer::update.system().after("update"))
                 .with_system(highlights::update.system().after("update"))
                 .with_system(board::update.system().label("pieces").after("update"))
-                .with_system(board::update_drag.system().after("pieces")),
+                .with_system(board::update_drag.system().after("pieces"))
+                .with_system(pocket::update.system().after("update")),
         )
         .add_system_set(
             SystemSet::on_enter(GameState::Promoting).with_system(ui::setup_promotion.system()),
@@ -78,6 +105,31 @@ fn main() {
         .add_system_set(
             SystemSet::on_exit(GameState::End).with_system(ui::destruct_end_screen.system()),
         )
+        .add_system_set(
+            SystemSet::on_enter(GameState::NewGame).with_system(ui::setup_new_game.system()),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::NewGame).with_system(ui::update_new_game.system()),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::NewGame).with_system(ui::destruct_new_game.system()),
+        )
+        .add_system_set(
+            SystemSet::on_enter(GameState::Setup)
+                .with_system(editor::setup_editor.system())
+                .with_system(highlights::clear.system()),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Setup)
+                .with_system(editor::update_buttons.system().label("edit"))
+                .with_system(editor::update_input.system().label("edit"))
+                .with_system(editor::update_panel.system().after("edit"))
+                .with_system(update_orientation.system().before("edit"))
+                .with_system(editor::update_board.system().after("edit")),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Setup).with_system(editor::destruct_editor.system()),
+        )
         .run();
 }
 
@@ -85,6 +137,13 @@ pub struct Selected(Option<Position>);
 pub struct FontAsset(Handle<Font>);
 struct Toggle(bool);
 
+// Whether Black is at the bottom of the board, and whether that follows the
+// game or only changes when asked.
+pub struct Orientation {
+    pub flipped: bool,
+    pub auto: bool,
+}
+
 impl FromWorld for FontAsset {
     fn from_world(world: &mut World) -> Self {
         let asset_server = world.get_resource::<AssetServer>().unwrap();
@@ -99,6 +158,10 @@ fn setup(mut commands: Commands) {
     commands.insert_resource(BoardStates::new());
     commands.insert_resource(Toggle(false));
     commands.insert_resource(Selected(None));
+    commands.insert_resource(Orientation {
+        flipped: false,
+        auto: false,
+    });
 }
 
 fn update(
@@ -107,7 +170,11 @@ fn update(
     mut selected: ResMut<Selected>,
     mut highlights: ResMut<Highlights>,
     mut drag: ResMut<Drag>,
+    mut dropping: ResMut<pocket::Dropping>,
     mut toggle: ResMut<Toggle>,
+    mut clocks: ResMut<clock::Clocks>,
+    players: Res<computer::Players>,
+    orientation: Res<Orientation>,
     windows: Res<Windows>,
     mouse_input: Res<Input<MouseButton>>,
 ) {
@@ -117,11 +184,61 @@ fn update(
     } else {
         return;
     };
+    if computer::to_move(&players, &states) {
+        return;
+    }
 
     if mouse_input.just_pressed(MouseButton::Left) {
-        if let Some(pos) = to_board_pos(mouse_pos) {
+        if let Some(variant) = pocket::at(mouse_pos, &states.active().state, orientation.flipped) {
             if let Some(s) = selected.0 {
-                if try_move(&mut states, &mut game_state, s, pos) {
+                *drag = Drag::Reset(s);
+                selected.0 = None;
+            }
+            dropping.variant = Some(variant);
+            return;
+        }
+    }
+    if let Some(variant) = dropping.variant {
+        let pos = to_board_pos(mouse_pos, orientation.flipped);
+        if mouse_input.just_pressed(MouseButton::Left) {
+            *dropping = pocket::Dropping::default();
+            if let Some(pos) = pos {
+                if try_drop(&mut states, &mut clocks, &mut game_state, variant, pos) {
+                    return;
+                }
+            }
+        } else if mouse_input.pressed(MouseButton::Left) {
+            dropping.drag = Some(mouse_pos);
+            let new = pos.filter(|pos| states.active().state.drop_squares(variant).contains(pos));
+            if highlights.selected_move != new {
+                highlights.selected_move = new;
+            }
+            return;
+        } else {
+            if mouse_input.just_released(MouseButton::Left) {
+                if let Some(pos) = pos {
+                    if try_drop(&mut states, &mut clocks, &mut game_state, variant, pos) {
+                        *dropping = pocket::Dropping::default();
+                    }
+                }
+            }
+            if mouse_input.just_pressed(MouseButton::Right) {
+                dropping.variant = None;
+            }
+            if dropping.drag.is_some() {
+                dropping.drag = None;
+            }
+            if highlights.selected_move.is_some() {
+                highlights.selected_move = None;
+            }
+            return;
+        }
+    }
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        if let Some(pos) = to_board_pos(mouse_pos, orientation.flipped) {
+            if let Some(s) = selected.0 {
+                if try_move(&mut states, &mut clocks, &mut game_state, s, pos) {
                     selected.0 = None;
                 } else if s == pos {
                     toggle.0 = true;
@@ -144,7 +261,7 @@ fn update(
         if let Some(s) = selected.0 {
             *drag = Drag::Mouse(mouse_pos);
             let mut new = None;
-            if let Some(pos) = to_board_pos(mouse_pos) {
+            if let Some(pos) = to_board_pos(mouse_pos, orientation.flipped) {
                 if states
                     .active()
                     .state
@@ -162,8 +279,8 @@ fn update(
         }
     } else if mouse_input.just_released(MouseButton::Left) {
         if let Some(s) = selected.0 {
-            if let Some(pos) = to_board_pos(mouse_pos) {
-                if try_move(&mut states, &mut game_state, s, pos) {
+            if let Some(pos) = to_board_pos(mouse_pos, orientation.flipped) {
+                if try_move(&mut states, &mut clocks, &mut game_state, s, pos) {
                     selected.0 = None;
                 } else if pos == s {
                     *drag = Drag::Reset(s);
@@ -200,17 +317,24 @@ fn update(
     }
 }
 
+// Plays a move on the board. When a pawn reaches the last rank it is promoted
+// to `promotion` if one is given, otherwise the player is asked to choose.
 fn try_move(
     states: &mut BoardStates,
+    clocks: &mut clock::Clocks,
     game_state: &mut State<GameState>,
     from: Position,
     to: Position,
 ) -> bool {
     if let Some(new) = states.active().try_move(from, to) {
+        let colour = states.active().state.get_turn();
         states.add(new);
         if states.active().state.promoting() {
             game_state.set(GameState::Promoting).unwrap();
-        } else if states.active().state.ended().is_some() {
+            return true;
+        }
+        clock::press(clocks, states, colour);
+        if states.active().state.ended().is_some() {
             game_state.set(GameState::End).unwrap();
         }
         return true;
@@ -218,19 +342,94 @@ fn try_move(
     false
 }
 
-fn to_board_pos(pos: Vec2) -> Option<Position> {
-    Position::from_xy(
-        ((pos.x - CENTRE_X) / SQUARE_SIZE + 5.0) as i8 - 1,
-        ((pos.y - CENTRE_Y) / SQUARE_SIZE + 5.0) as i8 - 1,
-    )
+// Drops a piece from the pocket of the side to move.
+fn try_drop(
+    states: &mut BoardStates,
+    clocks: &mut clock::Clocks,
+    game_state: &mut State<GameState>,
+    variant: Variant,
+    to: Position,
+) -> bool {
+    if let Some(new) = states.active().try_drop(variant, to) {
+        let colour = states.active().state.get_turn();
+        states.add(new);
+        clock::press(clocks, states, colour);
+        if states.active().state.ended().is_some() {
+            game_state.set(GameState::End).unwrap();
+        }
+        return true;
+    }
+    false
 }
 
-fn from_board_pos(pos: Position, z: f32) -> Transform {
-    Transform::from_xyz(
-        (pos.get_x() as f32 - 3.5) * SQUARE_SIZE + CENTRE_X,
-        (pos.get_y() as f32 - 3.5) * SQUARE_SIZE + CENTRE_Y,
-        z,
-    )
+// Plays a move chosen by the computer or an engine.
+fn play_move(
+    states: &mut BoardStates,
+    clocks: &mut clock::Clocks,
+    game_state: &mut State<GameState>,
+    mv: Move,
+) -> bool {
+    if let Some(new) = states.active().apply(mv) {
+        let colour = states.active().state.get_turn();
+        states.add(new);
+        clock::press(clocks, states, colour);
+        if states.active().state.ended().is_some() {
+            game_state.set(GameState::End).unwrap();
+        }
+        return true;
+    }
+    false
+}
+
+// F flips the board and Shift+F turns auto-flip on or off. Auto-flip faces
+// the side to move when two people are playing, and otherwise the person
+// playing the computer.
+fn update_orientation(
+    mut orientation: ResMut<Orientation>,
+    states: Res<BoardStates>,
+    players: Res<computer::Players>,
+    keys: Res<Input<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::F) {
+        if keys.pressed(KeyCode::LShift) || keys.pressed(KeyCode::RShift) {
+            orientation.auto = !orientation.auto;
+        } else {
+            orientation.auto = false;
+            orientation.flipped = !orientation.flipped;
+        }
+    }
+    if orientation.auto {
+        let human = |colour| players.get(colour) == computer::Player::Human;
+        let facing = match (human(White), human(Black)) {
+            (true, true) => Some(states.active().state.get_turn()),
+            (true, false) => Some(White),
+            (false, true) => Some(Black),
+            (false, false) => None,
+        };
+        if let Some(colour) = facing {
+            if orientation.flipped != (colour == Black) {
+                orientation.flipped = colour == Black;
+            }
+        }
+    }
+}
+
+fn to_board_pos(pos: Vec2, flipped: bool) -> Option<Position> {
+    let x = ((pos.x - CENTRE_X) / SQUARE_SIZE + 5.0) as i8 - 1;
+    let y = ((pos.y - CENTRE_Y) / SQUARE_SIZE + 5.0) as i8 - 1;
+    if flipped {
+        Position::from_xy(7 - x, 7 - y)
+    } else {
+        Position::from_xy(x, y)
+    }
+}
+
+fn from_board_pos(pos: Position, z: f32, flipped: bool) -> Transform {
+    if flipped {
+        from_xy(7 - pos.get_x(), 7 - pos.get_y(), z)
+    } else {
+        from_xy(pos.get_x(), pos.get_y(), z)
+    }
 }
 
 fn from_xy(x: i8, y: i8, z: f32) -> Transform {
@@ -0,0 +1,246 @@
+use super::*;
+use bevy::{input::mouse::MouseWheel, ui::FocusPolicy};
+
+const ROW_HEIGHT: f32 = 26.0;
+const MARGIN: f32 = 10.0;
+const INDENT: f32 = 16.0;
+const NUMBER_WIDTH: f32 = 44.0;
+const MOVE_WIDTH: f32 = 72.0;
+const VISIBLE_ROWS: usize = ((SCREEN_HEIGHT - clock::HEIGHT - MARGIN * 2.0) / ROW_HEIGHT) as usize;
+
+pub struct MoveList {
+    panel: Entity,
+    rows: Vec<Entity>,
+    row_count: usize,
+    scroll: usize,
+}
+pub struct MoveButton(usize);
+
+impl MoveList {
+    pub fn panel(&self) -> Entity {
+        self.panel
+    }
+}
+
+pub struct MoveListMaterials {
+    background: Handle<ColorMaterial>,
+    normal: Handle<ColorMaterial>,
+    hovered: Handle<ColorMaterial>,
+    current: Handle<ColorMaterial>,
+}
+
+pub fn setup(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    let list_materials = MoveListMaterials {
+        background: materials.add(Color::rgb(0.15, 0.14, 0.13).into()),
+        normal: materials.add(Color::NONE.into()),
+        hovered: materials.add(Color::rgba(0.4, 0.4, 0.4, 1.0).into()),
+        current: materials.add(Color::rgba(0.6, 0.55, 0.3, 1.0).into()),
+    };
+
+    let panel = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(PANEL_WIDTH), Val::Px(SCREEN_HEIGHT)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(SCREEN_WIDTH - PANEL_WIDTH),
+                    bottom: Val::Px(0.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material: list_materials.background.clone(),
+            ..Default::default()
+        })
+        .id();
+
+    commands.insert_resource(MoveList {
+        panel,
+        rows: Vec::new(),
+        row_count: 0,
+        scroll: 0,
+    });
+    commands.insert_resource(list_materials);
+}
+
+pub fn update(
+    mut commands: Commands,
+    mut move_list: ResMut<MoveList>,
+    mut wheel: EventReader<MouseWheel>,
+    states: Res<BoardStates>,
+    materials: Res<MoveListMaterials>,
+    font: Res<FontAsset>,
+) {
+    let mut scroll = move_list.scroll;
+    for event in wheel.iter() {
+        if event.y > 0.0 {
+            scroll = scroll.saturating_sub(1);
+        } else if event.y < 0.0 {
+            scroll += 1;
+        }
+    }
+    scroll = scroll.min(move_list.row_count.saturating_sub(VISIBLE_ROWS));
+
+    // Laying out the rows works out the SAN of every move in the game, so it
+    // is only done when the game changes or the list scrolls.
+    if !states.is_changed() && scroll == move_list.scroll {
+        return;
+    }
+    let rows = states.move_rows();
+    move_list.row_count = rows.len();
+    if states.is_changed() {
+        let current = states.current();
+        if let Some(row) = rows
+            .iter()
+            .position(|row| row.items.iter().any(|&(_, node)| node == Some(current)))
+        {
+            if row < scroll {
+                scroll = row;
+            } else if row >= scroll + VISIBLE_ROWS {
+                scroll = row + 1 - VISIBLE_ROWS;
+            }
+        }
+        scroll = scroll.min(rows.len().saturating_sub(VISIBLE_ROWS));
+    }
+    move_list.scroll = scroll;
+
+    for entity in move_list.rows.drain(..) {
+        commands.entity(entity).despawn_recursive();
+    }
+    let current = states.current();
+    let mut entities = Vec::new();
+    for (i, row) in rows.iter().skip(scroll).take(VISIBLE_ROWS).enumerate() {
+        let entity = commands
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    size: Size::new(
+                        Val::Px(PANEL_WIDTH - MARGIN * 2.0 - INDENT * row.depth as f32),
+                        Val::Px(ROW_HEIGHT),
+                    ),
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        left: Val::Px(MARGIN + INDENT * row.depth as f32),
+                        bottom: Val::Px(
+                            SCREEN_HEIGHT - clock::HEIGHT - MARGIN - ROW_HEIGHT * (i + 1) as f32,
+                        ),
+                        ..Default::default()
+                    },
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                material: materials.normal.clone(),
+                ..Default::default()
+            })
+            .with_children(|parent| {
+                for (text, node) in row.items.iter() {
+                    match *node {
+                        Some(node) => move_button(
+                            parent,
+                            &materials,
+                            &font,
+                            text,
+                            node,
+                            node == current,
+                            row.depth,
+                        ),
+                        None => {
+                            parent.spawn_bundle(TextBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(NUMBER_WIDTH), Val::Auto),
+                                    ..Default::default()
+                                },
+                                text: move_text(text, &font, row.depth),
+                                ..Default::default()
+                            });
+                        }
+                    }
+                }
+            })
+            .id();
+        entities.push(entity);
+    }
+    commands.entity(move_list.panel).push_children(&entities);
+    move_list.rows = entities;
+}
+
+fn move_button(
+    parent: &mut ChildBuilder,
+    materials: &MoveListMaterials,
+    font: &FontAsset,
+    text: &str,
+    node: usize,
+    current: bool,
+    depth: usize,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(MOVE_WIDTH), Val::Px(ROW_HEIGHT - 2.0)),
+                align_items: AlignItems::Center,
+                padding: Rect {
+                    left: Val::Px(4.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material: if current {
+                materials.current.clone()
+            } else {
+                materials.normal.clone()
+            },
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: move_text(text, font, depth),
+                    ..Default::default()
+                })
+                .insert(FocusPolicy::Pass);
+        })
+        .insert(MoveButton(node));
+}
+
+fn move_text(text: &str, font: &FontAsset, depth: usize) -> Text {
+    Text::with_section(
+        text,
+        TextStyle {
+            font: font.0.clone(),
+            font_size: if depth == 0 { 20.0 } else { 18.0 },
+            color: if depth == 0 {
+                Color::rgba(1.0, 1.0, 1.0, 0.8)
+            } else {
+                Color::rgba(1.0, 1.0, 1.0, 0.55)
+            },
+        },
+        Default::default(),
+    )
+}
+
+pub fn update_buttons(
+    mut states: ResMut<BoardStates>,
+    mut selected: ResMut<Selected>,
+    materials: Res<MoveListMaterials>,
+    mut buttons: Query<
+        (&Interaction, &mut Handle<ColorMaterial>, &MoveButton),
+        Changed<Interaction>,
+    >,
+) {
+    for (interaction, mut material, button) in buttons.iter_mut() {
+        if button.0 == states.current() {
+            continue;
+        }
+        match *interaction {
+            Interaction::Clicked => {
+                states.go_to(button.0);
+                selected.0 = None;
+            }
+            Interaction::Hovered => {
+                *material = materials.hovered.clone();
+            }
+            Interaction::None => {
+                *material = materials.normal.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use super::*;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DIRECTORY: &str = "games";
+
+pub struct Library {
+    games: Vec<Game>,
+    current: usize,
+}
+
+pub fn setup(mut commands: Commands) {
+    commands.insert_resource(Library {
+        games: Vec::new(),
+        current: 0,
+    });
+}
+
+pub fn update(
+    mut states: ResMut<BoardStates>,
+    mut library: ResMut<Library>,
+    mut clocks: ResMut<clock::Clocks>,
+    mut game_state: ResMut<State<GameState>>,
+    mut selected: ResMut<Selected>,
+    mut windows: ResMut<Windows>,
+    mut dropped: EventReader<FileDragAndDrop>,
+    mut started: Local<bool>,
+    keys: Res<Input<KeyCode>>,
+) {
+    let mut path = None;
+    if !*started {
+        *started = true;
+        path = env::args().nth(1).map(PathBuf::from);
+    }
+    for event in dropped.iter() {
+        if let FileDragAndDrop::DroppedFile { path_buf, .. } = event {
+            path = Some(path_buf.clone());
+        }
+    }
+
+    let mut index = None;
+    if let Some(path) = path {
+        let title = match read(&path) {
+            Ok(games) => {
+                library.games = games;
+                index = Some(0);
+                None
+            }
+            Err(error) => Some(format!("Chess - {}: {}", path.display(), error)),
+        };
+        if let Some(title) = title {
+            windows.get_primary_mut().unwrap().set_title(title);
+        }
+    }
+
+    let control = keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl);
+    if control && keys.just_pressed(KeyCode::S) {
+        save(&states, &mut windows);
+    }
+    if keys.just_pressed(KeyCode::PageDown) && library.current + 1 < library.games.len() {
+        index = Some(library.current + 1);
+    }
+    if keys.just_pressed(KeyCode::PageUp) && library.current > 0 && !library.games.is_empty() {
+        index = Some(library.current - 1);
+    }
+
+    if let Some(index) = index {
+        library.current = index;
+        let game = &library.games[index];
+        *states = BoardStates::from_game(game);
+        clocks.clear();
+        selected.0 = None;
+        let _ = game_state.overwrite_set(GameState::Playing);
+        windows.get_primary_mut().unwrap().set_title(format!(
+            "Chess - {} vs {} ({}/{})",
+            game.tag("White").unwrap_or("?"),
+            game.tag("Black").unwrap_or("?"),
+            index + 1,
+            library.games.len()
+        ));
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<Game>, String> {
+    let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let games = Game::parse_all(&text).map_err(|error| error.to_string())?;
+    if games.is_empty() {
+        return Err("no games found".to_string());
+    }
+    Ok(games)
+}
+
+// Reports where the game was saved, or why it could not be, in the title.
+pub fn save(states: &BoardStates, windows: &mut Windows) {
+    let title = match write(states) {
+        Ok(path) => format!("Chess - saved to {}", path.display()),
+        Err(error) => format!("Chess - could not save game: {}", error),
+    };
+    windows.get_primary_mut().unwrap().set_title(title);
+}
+
+fn write(states: &BoardStates) -> io::Result<PathBuf> {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil_date(seconds / 86400);
+    let time = seconds % 86400;
+
+    let mut game = states.to_game();
+    game.set_tag("Event", "Casual game");
+    game.set_tag("Site", "Chess");
+    game.set_tag("Date", &format!("{:04}.{:02}.{:02}", year, month, day));
+    let pgn = game
+        .to_pgn()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "game has an illegal move"))?;
+
+    fs::create_dir_all(DIRECTORY)?;
+    let path = PathBuf::from(DIRECTORY).join(format!(
+        "{:04}-{:02}-{:02}_{:02}-{:02}-{:02}.pgn",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    ));
+    fs::write(&path, pgn)?;
+    Ok(path)
+}
+
+// Converts days since 1970-01-01 to a (year, month, day) date in UTC.
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
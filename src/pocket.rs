@@ -0,0 +1,153 @@
+use super::*;
+
+const SLOT_SIZE: f32 = 40.0;
+const POCKET_X: f32 = CENTRE_X + SQUARE_SIZE * 4.0 + 22.0;
+const VARIANTS: [Variant; 5] = [Pawn, Knight, Bishop, Rook, Queen];
+
+pub struct PocketPiece(Piece);
+pub struct PocketCount(Piece);
+
+// The piece the side to move has picked out of its pocket, and where it is
+// being dragged to.
+#[derive(Default)]
+pub struct Dropping {
+    pub variant: Option<Variant>,
+    pub drag: Option<Vec2>,
+}
+
+pub fn setup(mut commands: Commands, textures: Res<board::Textures>, font: Res<FontAsset>) {
+    commands.insert_resource(Dropping::default());
+
+    let style = TextStyle {
+        font: font.0.clone(),
+        font_size: 16.0,
+        color: Color::rgb(0.95, 0.95, 0.95),
+    };
+    let hidden = Visible {
+        is_visible: false,
+        is_transparent: true,
+    };
+
+    for &colour in [White, Black].iter() {
+        for &variant in VARIANTS.iter() {
+            let piece = Piece { colour, variant };
+            commands
+                .spawn_bundle(SpriteBundle {
+                    material: textures.0.get(&piece).unwrap().clone(),
+                    transform: slot_transform(piece, false),
+                    visible: hidden.clone(),
+                    ..Default::default()
+                })
+                .insert(PocketPiece(piece));
+
+            commands
+                .spawn_bundle(Text2dBundle {
+                    text: Text::with_section(
+                        "",
+                        style.clone(),
+                        TextAlignment {
+                            vertical: VerticalAlign::Center,
+                            horizontal: HorizontalAlign::Center,
+                        },
+                    ),
+                    transform: count_transform(piece, false),
+                    visible: hidden.clone(),
+                    ..Default::default()
+                })
+                .insert(PocketCount(piece));
+        }
+    }
+}
+
+// The pockets sit to the right of the board in place of the rank labels,
+// each next to its own side of the board.
+fn slot_pos(piece: Piece, flipped: bool) -> Vec2 {
+    let offset = (piece.variant as usize as f32 + 0.5) * SLOT_SIZE - SQUARE_SIZE * 4.0;
+    if (piece.colour == White) != flipped {
+        Vec2::new(POCKET_X, CENTRE_Y + offset)
+    } else {
+        Vec2::new(POCKET_X, CENTRE_Y - offset)
+    }
+}
+
+fn slot_transform(piece: Piece, flipped: bool) -> Transform {
+    let slot = slot_pos(piece, flipped);
+    let mut transform = Transform::from_xyz(slot.x, slot.y, 2.0);
+    transform.scale = Vec3::new(0.5, 0.5, 1.0);
+    transform
+}
+
+fn count_transform(piece: Piece, flipped: bool) -> Transform {
+    let slot = slot_pos(piece, flipped);
+    Transform::from_xyz(slot.x + 15.0, slot.y - 13.0, 4.0)
+}
+
+// The piece in the pocket of the side to move under the cursor, if there is
+// one to drop.
+pub fn at(pos: Vec2, state: &chess_core::State, flipped: bool) -> Option<Variant> {
+    if !state.rules().drops() {
+        return None;
+    }
+    VARIANTS.iter().copied().find(|&variant| {
+        let slot = slot_pos(
+            Piece {
+                colour: state.get_turn(),
+                variant,
+            },
+            flipped,
+        );
+        (pos.x - slot.x).abs() < SLOT_SIZE / 2.0
+            && (pos.y - slot.y).abs() < SLOT_SIZE / 2.0
+            && state.pocket(state.get_turn(), variant) > 0
+    })
+}
+
+pub fn update(
+    states: Res<BoardStates>,
+    orientation: Res<Orientation>,
+    mut dropping: ResMut<Dropping>,
+    mut pieces: Query<(&PocketPiece, &mut Visible, &mut Transform)>,
+    mut counts: Query<
+        (&PocketCount, &mut Visible, &mut Text, &mut Transform),
+        Without<PocketPiece>,
+    >,
+    mut labels: Query<
+        &mut Visible,
+        (
+            With<board::RankLabel>,
+            Without<PocketPiece>,
+            Without<PocketCount>,
+        ),
+    >,
+) {
+    if states.is_changed() && dropping.variant.is_some() {
+        *dropping = Dropping::default();
+    }
+
+    if states.is_changed() || dropping.is_changed() || orientation.is_changed() {
+        let state = &states.active().state;
+        let drops = state.rules().drops();
+
+        for (PocketPiece(piece), mut visible, mut transform) in pieces.iter_mut() {
+            visible.is_visible = drops && state.pocket(piece.colour, piece.variant) > 0;
+            *transform = match dropping.drag {
+                Some(pos)
+                    if dropping.variant == Some(piece.variant)
+                        && piece.colour == state.get_turn() =>
+                {
+                    Transform::from_xyz(pos.x, pos.y, 3.0)
+                }
+                _ => slot_transform(*piece, orientation.flipped),
+            };
+        }
+        for (PocketCount(piece), mut visible, mut text, mut transform) in counts.iter_mut() {
+            let count = state.pocket(piece.colour, piece.variant);
+            visible.is_visible = drops && count > 0;
+            text.sections[0].value = count.to_string();
+            *transform = count_transform(*piece, orientation.flipped);
+        }
+        for mut visible in labels.iter_mut() {
+            visible.is_visible = !drops;
+        }
+    }
+}
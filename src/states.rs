@@ -1,90 +1,247 @@
 use super::*;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 pub struct BoardState {
-    pub state: chess::State,
+    pub state: chess_core::State,
     pub piece_moves: HashMap<Position, HashSet<Position>>,
     pub last_move: Option<(Position, Position)>,
+    pub mv: Option<Move>,
+    pub clock: Option<Duration>,
 }
 
 impl BoardState {
-    fn new() -> BoardState {
-        let state = chess::State::new();
+    fn new(state: chess_core::State) -> BoardState {
         let piece_moves = state.get_piece_moves();
         BoardState {
             state,
             piece_moves,
             last_move: None,
+            mv: None,
+            clock: None,
         }
     }
 
+    // A promotion is recorded as a queen until `promote` is called, which is
+    // also what it stays as if the pawn is blown up in Atomic.
     pub fn try_move(&self, from: Position, to: Position) -> Option<BoardState> {
-        if self.state.is_valid_move(from, to) {
-            let mut state = self.state.clone();
-            state.try_move(from, to);
-            let piece_moves = state.get_piece_moves();
-            return Some(BoardState {
-                state,
-                piece_moves,
-                last_move: Some((from, to)),
-            });
+        let mv = self
+            .state
+            .find_move(from, to, None)
+            .or_else(|| self.state.find_move(from, to, Some(Queen)))?;
+        let mut state = self.state.clone();
+        state.try_move(from, to);
+        let piece_moves = state.get_piece_moves();
+        Some(BoardState {
+            state,
+            piece_moves,
+            last_move: Some((from, to)),
+            mv: Some(mv),
+            clock: None,
+        })
+    }
+
+    // A drop is recorded as a last move that starts and ends on the same
+    // square.
+    pub fn try_drop(&self, variant: Variant, to: Position) -> Option<BoardState> {
+        self.apply(self.state.find_drop(variant, to)?)
+    }
+
+    // Plays a move including any promotion.
+    pub fn apply(&self, mv: Move) -> Option<BoardState> {
+        let mut state = self.state.clone();
+        if !state.apply(mv) {
+            return None;
         }
-        None
+        let from = match mv.kind {
+            MoveKind::Drop(_) => mv.to,
+            _ => mv.from,
+        };
+        let piece_moves = state.get_piece_moves();
+        Some(BoardState {
+            state,
+            piece_moves,
+            last_move: Some((from, mv.to)),
+            mv: Some(mv),
+            clock: None,
+        })
     }
 
     pub fn promote(&mut self, variant: Variant) -> bool {
         if self.state.promote(variant) {
+            self.piece_moves = self.state.get_piece_moves();
+            self.mv = self.mv.map(|mv| Move {
+                promotion: Some(variant),
+                ..mv
+            });
+            return true;
+        }
+        false
+    }
+
+    pub fn claim_fifty_moves(&mut self) -> bool {
+        if self.state.claim_fifty_moves() {
             self.piece_moves = self.state.get_piece_moves();
             return true;
         }
         false
     }
+
+    pub fn flag_fall(&mut self, colour: Colour) {
+        self.state.flag_fall(colour);
+        self.piece_moves = self.state.get_piece_moves();
+    }
+}
+
+pub struct MoveRow {
+    pub depth: usize,
+    pub items: Vec<(String, Option<usize>)>,
 }
 
+struct TreeNode {
+    board_state: BoardState,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+// The game history as a tree. The first child of each node continues the
+// main line and any others are variations. Deleted nodes leave a `None` in
+// `nodes` so that the indices of the rest stay valid.
 pub struct BoardStates {
-    states: Vec<BoardState>,
+    nodes: Vec<Option<TreeNode>>,
     current: usize,
-    end: usize,
+    pub time_control: Option<TimeControl>,
 }
 
 impl BoardStates {
     pub fn new() -> BoardStates {
+        BoardStates::from_state(chess_core::State::new())
+    }
+
+    pub fn with_rules(rules: &'static dyn chess_core::Rules) -> BoardStates {
+        BoardStates::from_state(chess_core::State::with_rules(rules))
+    }
+
+    // A Chess960 game from a start position chosen by the system clock, which
+    // is random enough for picking one of 960.
+    pub fn chess960() -> BoardStates {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.subsec_nanos());
+        BoardStates::from_state(chess_core::State::chess960((nanos % 960) as u16).unwrap())
+    }
+
+    pub fn from_state(state: chess_core::State) -> BoardStates {
         BoardStates {
-            states: vec![BoardState::new()],
+            nodes: vec![Some(TreeNode {
+                board_state: BoardState::new(state),
+                parent: None,
+                children: Vec::new(),
+            })],
             current: 0,
-            end: 0,
+            time_control: None,
         }
     }
 
+    pub fn from_game(game: &Game) -> BoardStates {
+        let mut states = BoardStates::from_state(game.start.clone());
+        states.time_control = game.tag("TimeControl").and_then(TimeControl::parse);
+        states.add_line(&game.moves);
+        states.current = 0;
+        states
+    }
+
+    fn add_line(&mut self, line: &[Node]) {
+        for node in line {
+            let parent = self.current;
+            if !self.play(node.mv) {
+                return;
+            }
+            let main = self.current;
+            if let Some(comment) = &node.comment {
+                self.node_mut(main).board_state.clock = parse_clock(comment);
+            }
+            for variation in node.variations.iter() {
+                self.current = parent;
+                self.add_line(variation);
+            }
+            self.current = main;
+        }
+    }
+
+    fn play(&mut self, mv: Move) -> bool {
+        match self.active().apply(mv) {
+            Some(state) => {
+                self.add(state);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn node(&self, index: usize) -> &TreeNode {
+        self.nodes[index].as_ref().unwrap()
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut TreeNode {
+        self.nodes[index].as_mut().unwrap()
+    }
+
     pub fn add(&mut self, state: BoardState) {
-        self.current += 1;
-        self.end = self.current;
-        if self.states.len() > self.current {
-            self.states[self.current] = state;
-        } else {
-            self.states.push(state);
+        if !state.state.promoting() {
+            if let Some(existing) = self.find_child(self.current, &state) {
+                self.current = existing;
+                return;
+            }
         }
 
+        let index = self.nodes.len();
+        self.nodes.push(Some(TreeNode {
+            board_state: state,
+            parent: Some(self.current),
+            children: Vec::new(),
+        }));
+        self.node_mut(self.current).children.push(index);
+        self.current = index;
+
+        let hash = self.active().state.hash();
         let mut count = 0;
-        for previous in &self.states[..self.current] {
-            if previous.state == self.states[self.current].state {
+        let mut ancestor = self.node(index).parent;
+        while let Some(previous) = ancestor {
+            if self.node(previous).board_state.state.hash() == hash {
                 count += 1;
             }
+            ancestor = self.node(previous).parent;
         }
 
         if count >= 2 {
-            self.states[self.current].state.threefold_repetition();
+            self.node_mut(index)
+                .board_state
+                .state
+                .threefold_repetition();
         }
     }
 
+    // Finds a child of `parent` reached by the same move as `state`, other
+    // than `state` itself.
+    fn find_child(&self, parent: usize, state: &BoardState) -> Option<usize> {
+        self.node(parent).children.iter().copied().find(|&child| {
+            let other = &self.node(child).board_state;
+            !std::ptr::eq(other, state)
+                && other.last_move == state.last_move
+                && other.state.hash() == state.state.hash()
+                && !other.state.promoting()
+        })
+    }
+
     pub fn undo(&mut self) {
-        if self.current > 0 {
-            self.current -= 1;
+        if let Some(parent) = self.node(self.current).parent {
+            self.current = parent;
         }
     }
 
     pub fn redo(&mut self) {
-        if self.current < self.end {
-            self.current += 1;
+        if let Some(&child) = self.node(self.current).children.first() {
+            self.current = child;
         }
     }
 
@@ -97,14 +254,273 @@ impl BoardStates {
     }
 
     pub fn at_end(&self) -> bool {
-        self.current == self.end
+        self.node(self.current).children.is_empty()
+    }
+
+    fn siblings(&self) -> &[usize] {
+        match self.node(self.current).parent {
+            Some(parent) => &self.node(parent).children,
+            None => &[0],
+        }
+    }
+
+    pub fn has_siblings(&self) -> bool {
+        self.siblings().len() > 1
+    }
+
+    pub fn next_sibling(&mut self) {
+        let siblings = self.siblings();
+        let i = siblings.iter().position(|&s| s == self.current).unwrap();
+        self.current = siblings[(i + 1) % siblings.len()];
+    }
+
+    pub fn previous_sibling(&mut self) {
+        let siblings = self.siblings();
+        let i = siblings.iter().position(|&s| s == self.current).unwrap();
+        self.current = siblings[(i + siblings.len() - 1) % siblings.len()];
+    }
+
+    pub fn in_main_line(&self) -> bool {
+        let mut index = self.current;
+        while let Some(parent) = self.node(index).parent {
+            if self.node(parent).children[0] != index {
+                return false;
+            }
+            index = parent;
+        }
+        true
+    }
+
+    pub fn promote_variation(&mut self) {
+        let mut index = self.current;
+        while let Some(parent) = self.node(index).parent {
+            let children = &mut self.node_mut(parent).children;
+            let i = children.iter().position(|&c| c == index).unwrap();
+            let child = children.remove(i);
+            children.insert(0, child);
+            index = parent;
+        }
+    }
+
+    pub fn delete_branch(&mut self) {
+        let parent = match self.node(self.current).parent {
+            Some(parent) => parent,
+            None => return,
+        };
+        let deleted = self.current;
+        self.node_mut(parent).children.retain(|&c| c != deleted);
+        let mut stack = vec![deleted];
+        while let Some(index) = stack.pop() {
+            if let Some(node) = self.nodes[index].take() {
+                stack.extend(node.children);
+            }
+        }
+        self.current = parent;
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn go_to(&mut self, index: usize) {
+        if let Some(Some(_)) = self.nodes.get(index) {
+            self.current = index;
+        }
+    }
+
+    pub fn start(&self) -> &chess_core::State {
+        &self.node(0).board_state.state
     }
 
     pub fn active(&self) -> &BoardState {
-        &self.states[self.current]
+        &self.node(self.current).board_state
+    }
+
+    // The hashes of the positions before the current one, oldest first.
+    pub fn history(&self) -> Vec<u64> {
+        let mut history = Vec::new();
+        let mut ancestor = self.node(self.current).parent;
+        while let Some(index) = ancestor {
+            history.push(self.node(index).board_state.state.hash());
+            ancestor = self.node(index).parent;
+        }
+        history.reverse();
+        history
+    }
+
+    // The starting position and the moves played from it to the current one.
+    pub fn moves(&self) -> (chess_core::State, Vec<Move>) {
+        let mut moves = Vec::new();
+        let mut index = self.current;
+        while let Some(mv) = self.move_to(index) {
+            moves.push(mv);
+            index = self.node(index).parent.unwrap();
+        }
+        moves.reverse();
+        (self.node(0).board_state.state.clone(), moves)
     }
 
     pub fn promote(&mut self, variant: Variant) -> bool {
-        self.states[self.current].promote(variant)
+        let current = self.current;
+        if !self.node_mut(current).board_state.promote(variant) {
+            return false;
+        }
+        let parent = self.node(current).parent.unwrap();
+        if let Some(existing) = self.find_child(parent, &self.node(current).board_state) {
+            self.delete_branch();
+            self.current = existing;
+        }
+        true
+    }
+
+    pub fn claim_fifty_moves(&mut self) -> bool {
+        let current = self.current;
+        self.node_mut(current).board_state.claim_fifty_moves()
+    }
+
+    pub fn flag_fall(&mut self, colour: Colour) {
+        let current = self.current;
+        self.node_mut(current).board_state.flag_fall(colour);
+    }
+
+    pub fn set_clock(&mut self, remaining: Duration) {
+        let current = self.current;
+        self.node_mut(current).board_state.clock = Some(remaining);
+    }
+
+    // The move that led to a node, or `None` for the root or while a
+    // promotion is pending.
+    fn move_to(&self, index: usize) -> Option<Move> {
+        let board_state = &self.node(index).board_state;
+        if board_state.state.promoting() {
+            return None;
+        }
+        board_state.mv
+    }
+
+    // Lays out the history for the move list: one row per full move, with
+    // variations on indented rows below the move they are an alternative to.
+    pub fn move_rows(&self) -> Vec<MoveRow> {
+        let mut rows = Vec::new();
+        if let Some(&first) = self.node(0).children.first() {
+            self.add_rows(first, 0, &mut rows);
+        }
+        rows
+    }
+
+    fn add_rows(&self, first: usize, depth: usize, rows: &mut Vec<MoveRow>) {
+        let mut row = MoveRow {
+            depth,
+            items: Vec::new(),
+        };
+        let mut number = true;
+        let mut index = Some(first);
+        while let Some(i) = index {
+            let mv = match self.move_to(i) {
+                Some(mv) => mv,
+                None => break,
+            };
+            let parent = self.node(i).parent.unwrap();
+            let previous = &self.node(parent).board_state.state;
+            if previous.get_turn() == White {
+                if !row.items.is_empty() {
+                    rows.push(row);
+                    row = MoveRow {
+                        depth,
+                        items: Vec::new(),
+                    };
+                }
+                row.items
+                    .push((format!("{}.", previous.fullmove_number()), None));
+            } else if number {
+                row.items
+                    .push((format!("{}...", previous.fullmove_number()), None));
+            }
+            row.items.push((previous.to_san(mv).unwrap(), Some(i)));
+            number = false;
+
+            let siblings = &self.node(parent).children;
+            if siblings[0] == i && siblings.len() > 1 {
+                rows.push(row);
+                row = MoveRow {
+                    depth,
+                    items: Vec::new(),
+                };
+                for &sibling in siblings[1..].iter() {
+                    self.add_rows(sibling, depth + 1, rows);
+                }
+                number = true;
+            }
+            index = self.node(i).children.first().copied();
+        }
+        if !row.items.is_empty() {
+            rows.push(row);
+        }
+    }
+
+    fn line(&self, first: usize) -> (Vec<Node>, Option<EndState>) {
+        let mut line = Vec::new();
+        let mut ended = None;
+        let mut index = Some(first);
+        while let Some(i) = index {
+            let mv = match self.move_to(i) {
+                Some(mv) => mv,
+                None => break,
+            };
+            let mut node = Node::new(mv);
+            if let Some(clock) = self.node(i).board_state.clock {
+                node.comment = Some(format!("[%clk {}]", format_clock(clock)));
+            }
+            let siblings = &self.node(self.node(i).parent.unwrap()).children;
+            if siblings[0] == i {
+                for &sibling in siblings[1..].iter() {
+                    let (variation, _) = self.line(sibling);
+                    if !variation.is_empty() {
+                        node.variations.push(variation);
+                    }
+                }
+            }
+            line.push(node);
+            ended = self.node(i).board_state.state.ended().clone();
+            index = self.node(i).children.first().copied();
+        }
+        (line, ended)
+    }
+
+    pub fn to_game(&self) -> Game {
+        let mut game = Game::new(self.node(0).board_state.state.clone());
+        if let Some(time_control) = &self.time_control {
+            game.set_tag("TimeControl", &time_control.to_string());
+        }
+        if let Some(&first) = self.node(0).children.first() {
+            let (moves, ended) = self.line(first);
+            game.moves = moves;
+            game.ended = ended;
+        }
+        game
+    }
+}
+
+// Formats a clock time as h:mm:ss for a PGN `[%clk]` command.
+fn format_clock(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn parse_clock(comment: &str) -> Option<Duration> {
+    let start = comment.find("[%clk ")? + 6;
+    let end = start + comment[start..].find(']')?;
+    let mut seconds = 0.0;
+    for part in comment[start..end].trim().split(':') {
+        seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+    }
+    if !seconds.is_finite() || seconds < 0.0 {
+        return None;
     }
+    Some(Duration::from_secs_f64(seconds))
 }
@@ -3,6 +3,19 @@ use bevy::{prelude::*, ui::FocusPolicy};
 
 pub struct UIEntity(Entity);
 pub struct PromoteTo(Variant);
+pub struct NewGame {
+    pub players: [computer::Player; 2],
+    // Standard chess, then Chess960, then the other variants in `RULES`.
+    rules: usize,
+}
+
+const RULES_CHOICES: usize = chess_core::RULES.len() + 1;
+
+impl NewGame {
+    pub fn rules(&self) -> &'static dyn chess_core::Rules {
+        chess_core::RULES[self.rules.saturating_sub(1)]
+    }
+}
 
 pub struct UIMaterials {
     background: Handle<ColorMaterial>,
@@ -19,12 +32,25 @@ pub enum ButtonType {
     Undo,
     Redo,
     Restart,
+    Claim,
+    Save,
+    New,
+}
+
+#[derive(Clone, Copy)]
+pub enum DialogButton {
+    Player(Colour),
+    Rules,
+    Setup,
+    Start,
+    Cancel,
 }
 
 pub fn setup(
     mut commands: Commands,
     mut materials: ResMut<Assets<ColorMaterial>>,
     asset_server: Res<AssetServer>,
+    font: Res<FontAsset>,
 ) {
     let ui_materials = UIMaterials {
         background: materials.add(Color::rgba(0.1, 0.1, 0.1, 0.7).into()),
@@ -57,30 +83,56 @@ pub fn setup(
                 parent,
                 &ui_materials,
                 ui_materials.restart.clone(),
-                SQUARE_SIZE * 0.5,
+                SQUARE_SIZE * 0.15,
                 ButtonType::Restart,
             );
+            text_button(
+                parent,
+                &ui_materials,
+                &font,
+                "New",
+                SQUARE_SIZE * 1.5,
+                ButtonType::New,
+            );
+            text_button(
+                parent,
+                &ui_materials,
+                &font,
+                "Save",
+                SQUARE_SIZE * 2.85,
+                ButtonType::Save,
+            );
             button(
                 parent,
                 &ui_materials,
                 ui_materials.undo.clone(),
-                SQUARE_SIZE * 3.75,
+                SQUARE_SIZE * 4.2,
                 ButtonType::Undo,
             );
             button(
                 parent,
                 &ui_materials,
                 ui_materials.redo.clone(),
-                SQUARE_SIZE * 5.25,
+                SQUARE_SIZE * 5.55,
                 ButtonType::Redo,
             );
+            text_button(
+                parent,
+                &ui_materials,
+                &font,
+                "Draw",
+                SQUARE_SIZE * 6.9,
+                ButtonType::Claim,
+            );
         });
 
     commands.insert_resource(ui_materials);
 }
 
 pub fn update_buttons(
+    mut game_state: ResMut<State<GameState>>,
     mut states: ResMut<BoardStates>,
+    mut windows: ResMut<Windows>,
     materials: Res<UIMaterials>,
     mut buttons: Query<
         (&Interaction, &mut Handle<ColorMaterial>, &ButtonType),
@@ -94,11 +146,49 @@ pub fn update_buttons(
                 ButtonType::Undo => states.undo(),
                 ButtonType::Redo => states.redo(),
                 ButtonType::Restart => states.reset(),
+                ButtonType::Claim => {
+                    if states.claim_fifty_moves() {
+                        game_state.set(GameState::End).unwrap();
+                    }
+                }
+                ButtonType::Save => pgn::save(&states, &mut windows),
+                ButtonType::New => game_state.set(GameState::NewGame).unwrap(),
             }
         }
     }
 }
 
+pub fn update_keys(
+    mut states: ResMut<BoardStates>,
+    mut selected: ResMut<Selected>,
+    keys: Res<Input<KeyCode>>,
+) {
+    if keys.just_pressed(KeyCode::Left) && !states.at_start() {
+        states.undo();
+    }
+    if keys.just_pressed(KeyCode::Right) && !states.at_end() {
+        states.redo();
+    }
+    if keys.just_pressed(KeyCode::Home) && !states.at_start() {
+        states.reset();
+    }
+    if keys.just_pressed(KeyCode::Up) && states.has_siblings() {
+        states.previous_sibling();
+    }
+    if keys.just_pressed(KeyCode::Down) && states.has_siblings() {
+        states.next_sibling();
+    }
+    if keys.just_pressed(KeyCode::P) && !states.in_main_line() {
+        states.promote_variation();
+    }
+    if keys.just_pressed(KeyCode::Delete) && !states.at_start() {
+        states.delete_branch();
+    }
+    if states.is_changed() && selected.0.is_some() {
+        selected.0 = None;
+    }
+}
+
 pub fn update_greyed(
     states: Res<BoardStates>,
     mut buttons: Query<(&Interaction, &mut Handle<ColorMaterial>, &ButtonType)>,
@@ -121,6 +211,8 @@ fn update_button(
     if (*button_type == ButtonType::Undo && states.at_start())
         || (*button_type == ButtonType::Redo && states.at_end())
         || (*button_type == ButtonType::Restart && states.at_start())
+        || (*button_type == ButtonType::Claim && !states.active().state.can_claim_fifty_moves())
+        || (*button_type == ButtonType::Save && states.at_start() && states.at_end())
     {
         *material = materials.greyed.clone();
     } else {
@@ -136,6 +228,50 @@ fn update_button(
     }
 }
 
+fn text_button(
+    parent: &mut ChildBuilder,
+    materials: &UIMaterials,
+    font: &FontAsset,
+    text: &str,
+    position: f32,
+    button_type: ButtonType,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(60.0), Val::Px(60.0)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(position),
+                    ..Default::default()
+                },
+                margin: Rect::all(Val::Auto),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.greyed.clone(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        text,
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: 20.0,
+                            color: Color::rgba(1.0, 1.0, 1.0, 0.7),
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(FocusPolicy::Pass);
+        })
+        .insert(button_type);
+}
+
 fn button(
     parent: &mut ChildBuilder,
     materials: &UIMaterials,
@@ -182,14 +318,17 @@ pub fn setup_promotion(
     mut buttons: Query<&mut Handle<ColorMaterial>, With<ButtonType>>,
 ) {
     let colour = states.active().state.get_turn();
+    // Antichess lets pawns promote to a king as well, which needs a wider box.
+    let variants = states.active().state.rules().promotions();
+    let width = 80.0 * variants.len() as f32;
 
     let entity = commands
         .spawn_bundle(NodeBundle {
             style: Style {
-                size: Size::new(Val::Px(320.0), Val::Px(88.0)),
+                size: Size::new(Val::Px(width), Val::Px(88.0)),
                 position_type: PositionType::Absolute,
                 position: Rect {
-                    left: Val::Px(SCREEN_WIDTH / 2.0 + CENTRE_X - 160.0),
+                    left: Val::Px(SCREEN_WIDTH / 2.0 + CENTRE_X - width / 2.0),
                     bottom: Val::Px(SCREEN_HEIGHT / 2.0 + CENTRE_Y - 44.0),
                     ..Default::default()
                 },
@@ -200,38 +339,16 @@ pub fn setup_promotion(
             ..Default::default()
         })
         .with_children(|parent| {
-            promotion_button(
-                parent,
-                &materials,
-                Rect::all(Val::Auto),
-                &textures.0,
-                colour,
-                Knight,
-            );
-            promotion_button(
-                parent,
-                &materials,
-                Rect::all(Val::Auto),
-                &textures.0,
-                colour,
-                Bishop,
-            );
-            promotion_button(
-                parent,
-                &materials,
-                Rect::all(Val::Auto),
-                &textures.0,
-                colour,
-                Rook,
-            );
-            promotion_button(
-                parent,
-                &materials,
-                Rect::all(Val::Auto),
-                &textures.0,
-                colour,
-                Queen,
-            );
+            for &variant in variants {
+                promotion_button(
+                    parent,
+                    &materials,
+                    Rect::all(Val::Auto),
+                    &textures.0,
+                    colour,
+                    variant,
+                );
+            }
         })
         .id();
     commands.insert_resource(UIEntity(entity));
@@ -279,6 +396,7 @@ fn promotion_button(
 pub fn update_promotion(
     mut game_state: ResMut<State<GameState>>,
     mut states: ResMut<BoardStates>,
+    mut clocks: ResMut<clock::Clocks>,
     materials: Res<UIMaterials>,
     mut buttons: Query<
         (&Interaction, &mut Handle<ColorMaterial>, &PromoteTo),
@@ -288,7 +406,9 @@ pub fn update_promotion(
     for (interaction, mut material, to) in buttons.iter_mut() {
         match *interaction {
             Interaction::Clicked => {
+                let colour = states.active().state.get_turn();
                 states.promote(to.0);
+                clock::press(&mut clocks, &mut states, colour);
                 if states.active().state.ended().is_some() {
                     game_state.set(GameState::End).unwrap();
                 } else {
@@ -316,12 +436,27 @@ pub fn setup_end_screen(
     font: Res<FontAsset>,
     mut buttons: Query<(&mut Handle<ColorMaterial>, &ButtonType)>,
 ) {
-    let text = match states.active().state.ended().clone().unwrap() {
+    let state = &states.active().state;
+    let variant_text;
+    let text = match state.ended().clone().unwrap() {
         EndState::Checkmate(White) => "White wins by checkmate",
         EndState::Checkmate(Black) => "Black wins by checkmate",
         EndState::Stalemate => "Draw by stalemate",
         EndState::InsufficientMaterial => "Draw by insufficient material",
         EndState::ThreefoldRepetition => "Draw by threefold repetition",
+        EndState::FiftyMoveRule => "Draw by fifty-move rule",
+        EndState::SeventyFiveMoveRule => "Draw by 75-move rule",
+        EndState::Timeout(White) => "White wins on time",
+        EndState::Timeout(Black) => "Black wins on time",
+        EndState::TimeoutVsInsufficientMaterial => "Draw on time",
+        EndState::VariantWin(colour) => {
+            variant_text = format!("{} wins at {}", colour, state.rules().name());
+            &variant_text
+        }
+        EndState::VariantDraw => {
+            variant_text = format!("Draw at {}", state.rules().name());
+            &variant_text
+        }
     };
 
     let entity = commands
@@ -401,8 +536,12 @@ pub fn setup_end_screen(
 
     for (mut material, button_type) in buttons.iter_mut() {
         match *button_type {
-            ButtonType::Restart => *material = materials.normal.clone(),
-            ButtonType::Undo | ButtonType::Redo => *material = materials.greyed.clone(),
+            ButtonType::Restart | ButtonType::Save | ButtonType::New => {
+                *material = materials.normal.clone()
+            }
+            ButtonType::Undo | ButtonType::Redo | ButtonType::Claim => {
+                *material = materials.greyed.clone()
+            }
         }
     }
 }
@@ -410,6 +549,7 @@ pub fn setup_end_screen(
 pub fn update_end_screen(
     mut states: ResMut<BoardStates>,
     mut game_state: ResMut<State<GameState>>,
+    mut windows: ResMut<Windows>,
     materials: Res<UIMaterials>,
     mut buttons: Query<
         (&Interaction, &mut Handle<ColorMaterial>, &ButtonType),
@@ -417,7 +557,13 @@ pub fn update_end_screen(
     >,
     mut review_game: Query<
         (&Interaction, &mut Handle<ColorMaterial>),
-        (Changed<Interaction>, With<Button>, Without<ButtonType>),
+        (
+            Changed<Interaction>,
+            With<Button>,
+            Without<ButtonType>,
+            Without<move_list::MoveButton>,
+            Without<clock::ClockButton>,
+        ),
     >,
 ) {
     if let Ok((interaction, mut material)) = review_game.single_mut() {
@@ -435,12 +581,16 @@ pub fn update_end_screen(
     }
 
     for (interaction, mut material, button_type) in buttons.iter_mut() {
-        if *button_type == ButtonType::Restart {
+        if let ButtonType::Restart | ButtonType::Save | ButtonType::New = *button_type {
             match *interaction {
-                Interaction::Clicked => {
-                    states.reset();
-                    game_state.set(GameState::Playing).unwrap();
-                }
+                Interaction::Clicked => match *button_type {
+                    ButtonType::Save => pgn::save(&states, &mut windows),
+                    ButtonType::New => game_state.set(GameState::NewGame).unwrap(),
+                    _ => {
+                        states.reset();
+                        game_state.set(GameState::Playing).unwrap();
+                    }
+                },
                 Interaction::Hovered => {
                     *material = materials.hovered.clone();
                 }
@@ -455,3 +605,267 @@ pub fn update_end_screen(
 pub fn destruct_end_screen(mut commands: Commands, entity: Res<UIEntity>) {
     commands.entity(entity.0).despawn_recursive();
 }
+
+pub fn setup_new_game(
+    mut commands: Commands,
+    materials: Res<UIMaterials>,
+    players: Res<computer::Players>,
+    computer: Res<computer::Computer>,
+    states: Res<BoardStates>,
+    font: Res<FontAsset>,
+) {
+    let new_game = NewGame {
+        players: players.0,
+        rules: rules_choice(states.start()),
+    };
+    let entity = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(340.0), Val::Px(320.0)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(SCREEN_WIDTH / 2.0 + CENTRE_X - 170.0),
+                    bottom: Val::Px(SCREEN_HEIGHT / 2.0 + CENTRE_Y - 160.0),
+                    ..Default::default()
+                },
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            material: materials.background.clone(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        top: Val::Px(10.0),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                text: dialog_text("New game", &font, 30.0),
+                ..Default::default()
+            });
+            let choices = [
+                (White.to_string(), DialogButton::Player(White)),
+                (Black.to_string(), DialogButton::Player(Black)),
+                ("Rules".to_string(), DialogButton::Rules),
+                ("Position".to_string(), DialogButton::Setup),
+            ];
+            for (i, (label, button)) in choices.iter().enumerate() {
+                let top = 60.0 + 50.0 * i as f32;
+                parent.spawn_bundle(TextBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        position: Rect {
+                            top: Val::Px(top + 8.0),
+                            left: Val::Px(20.0),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    text: dialog_text(label, &font, 24.0),
+                    ..Default::default()
+                });
+                dialog_button(
+                    parent,
+                    &materials,
+                    &font,
+                    &choice_name(*button, &new_game, &computer),
+                    Rect {
+                        top: Val::Px(top),
+                        right: Val::Px(20.0),
+                        ..Default::default()
+                    },
+                    *button,
+                );
+            }
+            dialog_button(
+                parent,
+                &materials,
+                &font,
+                "Start",
+                Rect {
+                    bottom: Val::Px(10.0),
+                    left: Val::Px(20.0),
+                    ..Default::default()
+                },
+                DialogButton::Start,
+            );
+            dialog_button(
+                parent,
+                &materials,
+                &font,
+                "Cancel",
+                Rect {
+                    bottom: Val::Px(10.0),
+                    right: Val::Px(20.0),
+                    ..Default::default()
+                },
+                DialogButton::Cancel,
+            );
+        })
+        .id();
+    commands.insert_resource(UIEntity(entity));
+    commands.insert_resource(new_game);
+}
+
+// The rules choice that a game was started with. Chess960 is played by the
+// standard rules, so it is told apart by its start position.
+fn rules_choice(start: &chess_core::State) -> usize {
+    if start.is_chess960() {
+        return 1;
+    }
+    let name = start.rules().name();
+    match chess_core::RULES
+        .iter()
+        .position(|rules| rules.name() == name)
+    {
+        Some(0) | None => 0,
+        Some(i) => i + 1,
+    }
+}
+
+// The current choice shown on a button in the new game dialog.
+fn choice_name(button: DialogButton, new_game: &NewGame, computer: &computer::Computer) -> String {
+    match button {
+        DialogButton::Player(colour) => new_game.players[colour as usize].name(computer),
+        DialogButton::Rules => match new_game.rules {
+            1 => "Chess960".to_string(),
+            _ => new_game.rules().name().to_string(),
+        },
+        DialogButton::Setup => "Set up".to_string(),
+        DialogButton::Start => "Start".to_string(),
+        DialogButton::Cancel => "Cancel".to_string(),
+    }
+}
+
+fn dialog_text(text: &str, font: &FontAsset, font_size: f32) -> Text {
+    Text::with_section(
+        text,
+        TextStyle {
+            font: font.0.clone(),
+            font_size,
+            color: Color::rgba(1.0, 1.0, 1.0, 0.7),
+        },
+        Default::default(),
+    )
+}
+
+fn dialog_button(
+    parent: &mut ChildBuilder,
+    materials: &UIMaterials,
+    font: &FontAsset,
+    text: &str,
+    position: Rect<Val>,
+    button: DialogButton,
+) {
+    let (width, choice) = match button {
+        DialogButton::Player(_) | DialogButton::Rules => (210.0, true),
+        DialogButton::Setup => (210.0, false),
+        _ => (140.0, false),
+    };
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(width), Val::Px(40.0)),
+                position_type: PositionType::Absolute,
+                position,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.normal.clone(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            let mut text = parent.spawn_bundle(TextBundle {
+                text: dialog_text(text, font, 22.0),
+                ..Default::default()
+            });
+            text.insert(FocusPolicy::Pass);
+            if choice {
+                text.insert(button);
+            }
+        })
+        .insert(button);
+}
+
+pub fn update_new_game(
+    mut game_state: ResMut<State<GameState>>,
+    mut states: ResMut<BoardStates>,
+    mut clocks: ResMut<clock::Clocks>,
+    mut computer: ResMut<computer::Computer>,
+    mut players: ResMut<computer::Players>,
+    mut new_game: ResMut<NewGame>,
+    mut selected: ResMut<Selected>,
+    mut windows: ResMut<Windows>,
+    materials: Res<UIMaterials>,
+    mut buttons: Query<
+        (&Interaction, &mut Handle<ColorMaterial>, &DialogButton),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut texts: Query<(&mut Text, &DialogButton)>,
+) {
+    for (interaction, mut material, button) in buttons.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => match *button {
+                DialogButton::Player(colour) => {
+                    let player = &mut new_game.players[colour as usize];
+                    *player = player.next(&computer);
+                    update_choices(&mut texts, &new_game, &computer);
+                }
+                DialogButton::Rules => {
+                    new_game.rules = (new_game.rules + 1) % RULES_CHOICES;
+                    update_choices(&mut texts, &new_game, &computer);
+                }
+                DialogButton::Setup => {
+                    game_state.set(GameState::Setup).unwrap();
+                }
+                DialogButton::Start => {
+                    players.0 = new_game.players;
+                    *states = match new_game.rules {
+                        1 => BoardStates::chess960(),
+                        _ => BoardStates::with_rules(new_game.rules()),
+                    };
+                    clocks.restart(&mut states);
+                    computer.reset();
+                    selected.0 = None;
+                    windows
+                        .get_primary_mut()
+                        .unwrap()
+                        .set_title("Chess".to_string());
+                    game_state.set(GameState::Playing).unwrap();
+                }
+                DialogButton::Cancel => {
+                    if states.active().state.ended().is_some() {
+                        game_state.set(GameState::End).unwrap();
+                    } else {
+                        game_state.set(GameState::Playing).unwrap();
+                    }
+                }
+            },
+            Interaction::Hovered => {
+                *material = materials.hovered.clone();
+            }
+            Interaction::None => {
+                *material = materials.normal.clone();
+            }
+        }
+    }
+}
+
+fn update_choices(
+    texts: &mut Query<(&mut Text, &DialogButton)>,
+    new_game: &NewGame,
+    computer: &computer::Computer,
+) {
+    for (mut text, &button) in texts.iter_mut() {
+        text.sections[0].value = choice_name(button, new_game, computer);
+    }
+}
+
+pub fn destruct_new_game(mut commands: Commands, entity: Res<UIEntity>) {
+    commands.entity(entity.0).despawn_recursive();
+}